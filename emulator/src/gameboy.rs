@@ -0,0 +1,35 @@
+use lr35902::LR35902;
+use memory_bus::{io, MMU};
+use ppu::PPU;
+
+/// CPU, bus and PPU stepped together, one M-cycle at a time
+pub struct Gameboy {
+    pub cpu: LR35902,
+    pub mmu: MMU,
+    pub ppu: PPU,
+}
+
+impl Gameboy {
+    /// Starts executing from the boot ROM if one is mapped, otherwise from the cartridge entry point
+    pub fn new(mut mmu: MMU) -> Gameboy {
+        let mut cpu = LR35902::new();
+        if !mmu.is_boot_rom_mapped() {
+            cpu.skip_boot_rom();
+            // the boot ROM leaves the LCD on with the usual palette
+            mmu.write_byte(io::LCDC, 0x91);
+            mmu.write_byte(io::BGP, 0xFC);
+        }
+        cpu.init(&mut mmu);
+        Gameboy { cpu, mmu, ppu: PPU::default() }
+    }
+
+    /// Advances everything by one M-cycle
+    pub fn step(&mut self) {
+        if !self.mmu.is_cpu_stalled() {
+            self.cpu.step(&mut self.mmu, 2);
+            self.cpu.step(&mut self.mmu, 2);
+        }
+        self.ppu.step(&mut self.mmu, 4);
+        self.mmu.tick(4);
+    }
+}
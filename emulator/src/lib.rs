@@ -0,0 +1,9 @@
+mod gameboy;
+pub mod link;
+pub mod pacing;
+pub mod tcp_link;
+
+pub use gameboy::Gameboy;
+
+#[cfg(test)]
+mod tests;
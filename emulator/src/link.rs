@@ -0,0 +1,77 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use memory_bus::serial::SerialEndpoint;
+
+use crate::Gameboy;
+
+#[derive(Debug, Default)]
+struct Wire {
+    // byte each side has loaded while waiting for the other side's clock
+    waiting: [Option<u8>; 2],
+    // byte delivered to a waiting side, picked up on its next poll
+    delivered: [Option<u8>; 2],
+}
+
+/// One plug of an in-process link cable. Whichever side starts a transfer on its internal clock is the master, the
+/// other side only takes part if it has a transfer armed on the external clock by then, otherwise the master reads
+/// 0xFF as if nothing was plugged in.
+#[derive(Debug)]
+pub struct LinkEnd {
+    wire: Rc<RefCell<Wire>>,
+    side: usize,
+}
+
+impl LinkEnd {
+    pub fn pair() -> (LinkEnd, LinkEnd) {
+        let wire = Rc::new(RefCell::new(Wire::default()));
+        (LinkEnd { wire: wire.clone(), side: 0 }, LinkEnd { wire, side: 1 })
+    }
+}
+
+impl SerialEndpoint for LinkEnd {
+    fn exchange(&mut self, byte: u8) -> u8 {
+        let mut wire = self.wire.borrow_mut();
+        let other = 1 - self.side;
+        match wire.waiting[other].take() {
+            Some(received) => {
+                wire.delivered[other] = Some(byte);
+                received
+            }
+            None => 0xFF,
+        }
+    }
+
+    fn poll_external(&mut self, byte: u8) -> Option<u8> {
+        let mut wire = self.wire.borrow_mut();
+        match wire.delivered[self.side].take() {
+            Some(received) => Some(received),
+            None => {
+                wire.waiting[self.side] = Some(byte);
+                None
+            }
+        }
+    }
+}
+
+/// Two Game Boys with a cable between them, stepped in lockstep so runs are reproducible cycle for cycle. A byte sent
+/// by `left` reaches `right` in the same M-cycle, the other way around it arrives one M-cycle later.
+pub struct Link {
+    pub left: Gameboy,
+    pub right: Gameboy,
+}
+
+impl Link {
+    pub fn new(mut left: Gameboy, mut right: Gameboy) -> Link {
+        let (left_end, right_end) = LinkEnd::pair();
+        left.mmu.serial.connect(Box::new(left_end));
+        right.mmu.serial.connect(Box::new(right_end));
+        Link { left, right }
+    }
+
+    /// Advances both sides by one M-cycle
+    pub fn step(&mut self) {
+        self.left.step();
+        self.right.step();
+    }
+}
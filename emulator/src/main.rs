@@ -1,34 +1,208 @@
 use memory_bus::MMU;
-use lr35902::LR35902;
-use ppu::{PPU, PPUWindow};
+use memory_bus::access::Accuracy;
+use memory_bus::cartridge::{Cartridge, CartridgeEvent, SaveFile};
+use memory_bus::serial::Capture;
+use emulator::Gameboy;
+use emulator::pacing::{self, FramePacer, FrameSync, Speed};
+use emulator::tcp_link::{TcpLinkConfig, TcpLinkEndpoint};
+use ppu::PPUWindow;
+use ppu::bindings::{Bindings, Hotkey};
 
 use std::fs::File;
-use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::io::Read;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let mut mmu  = {
-        let mut mmu = MMU::new();
-        let dmg_rom = include_bytes!("D:/hobby/DMG_ROM.bin");
-        mmu.initialize(dmg_rom.iter().map(|x|*x));
-        mmu
-    };
+    let mut rom_path = None;
+    let mut boot_rom_path = None;
+    let mut accuracy = Accuracy::Strict;
+    let mut serial_stdout = false;
+    let mut link_listen = None;
+    let mut link_connect = None;
+    let mut link_config = TcpLinkConfig::default();
+    let mut headless = false;
+    let mut scale = 3;
+    let mut turbo = Speed::Uncapped;
+    let mut slow_motion = Speed::Times(0.25);
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--boot-rom" => boot_rom_path = args.next(),
+            "--lenient" => accuracy = Accuracy::Lenient,
+            "--serial-stdout" => serial_stdout = true,
+            "--link-listen" => link_listen = args.next(),
+            "--link-connect" => link_connect = args.next(),
+            "--link-latency" => {
+                let millis = args.next().ok_or("--link-latency needs milliseconds")?.parse()?;
+                link_config.latency = std::time::Duration::from_millis(millis);
+            }
+            "--link-max-skew" => {
+                link_config.max_skew = args.next().ok_or("--link-max-skew needs M-cycles")?.parse()?;
+            }
+            "--headless" => headless = true,
+            "--scale" => scale = args.next().ok_or("--scale needs a value")?.parse()?,
+            "--turbo" => turbo = match args.next().ok_or("--turbo needs a factor")?.parse::<u32>()? {
+                0 => Speed::Uncapped,
+                factor => Speed::Times(factor as f64),
+            },
+            "--slow-motion" => {
+                let divisor: u32 = args.next().ok_or("--slow-motion needs a divisor")?.parse()?;
+                slow_motion = Speed::Times(1.0 / divisor.max(1) as f64);
+            }
+            _ => rom_path = Some(arg),
+        }
+    }
 
-    let mut cpu = LR35902::new();
-    cpu.init(&mut mmu);
-    //let mut ppu_window = PPUWindow::new();
-    'update_loop: loop {
-        cpu.step(&mut mmu, 2);
-        //ppu_window.ppu.step(&mmu, cpu.clocks.current as usize);
-        cpu.step(&mut mmu, 2);
-        //if !ppu_window.update() { break 'update_loop; }
-        if cpu.clocks.total > 1_000_000_000 {
-            break 'update_loop;
+    let mut mmu = MMU::new();
+    mmu.accuracy = accuracy;
+    if serial_stdout {
+        mmu.serial.connect(Box::new(Capture::stdout()));
+    }
+    if let Some(port) = &link_listen {
+        println!("waiting for a link connection on port {}", port);
+        mmu.serial.connect(Box::new(TcpLinkEndpoint::listen(port.parse()?, link_config)?));
+    } else if let Some(addr) = &link_connect {
+        mmu.serial.connect(Box::new(TcpLinkEndpoint::connect(addr.as_str(), link_config)?));
+    }
+    if let Some(boot_rom_path) = &boot_rom_path {
+        let mut boot_rom = Vec::new();
+        File::open(boot_rom_path)?.read_to_end(&mut boot_rom)?;
+        mmu.load_boot_rom(boot_rom);
+    }
+
+    let mut save_file = None;
+    if let Some(rom_path) = &rom_path {
+        let mut rom = Vec::new();
+        File::open(rom_path)?.read_to_end(&mut rom)?;
+        let mut cartridge = Cartridge::new(rom)?;
+        if cartridge.supports_cgb() {
+            mmu.enable_cgb();
+        }
+        if cartridge.has_battery() {
+            let save = SaveFile::for_rom(rom_path);
+            save.load(&mut cartridge)?;
+            save_file = Some(save);
         }
+        mmu.insert_cartridge(cartridge);
     }
 
+    let title = match &mmu.cartridge {
+        Some(cartridge) if !cartridge.title().is_empty() => cartridge.title(),
+        _ => "Game Boy".to_string(),
+    };
+    let mut ppu_window = if headless {
+        None
+    } else {
+        Some(PPUWindow::new(&title, scale)?.with_bindings(Bindings::load("bindings.toml")?))
+    };
+    let mut gameboy = Gameboy::new(mmu);
+    let mut pacer: Box<dyn FrameSync> = Box::new(FramePacer::default());
+    let mut is_slow_motion = false;
+    let mut is_paused = false;
+    let mut frame_advance = false;
+    let mut rumble = false;
+    let mut infrared = false;
+    // errors leave the loop instead of returning, so battery RAM is flushed whatever happens
+    let result: Result<(), Box<dyn std::error::Error>> = 'update_loop: loop {
+        // a frame's worth of cycles, whether or not the LCD is on to show it
+        if !is_paused || std::mem::replace(&mut frame_advance, false) {
+            for _ in 0..pacing::CYCLES_PER_FRAME / 4 {
+                gameboy.step();
+                if let Some(hit) = gameboy.mmu.take_watch_hit() {
+                    println!("watchpoint {} hit at {:#06X} by PC {:#06X}: {:#04X} -> {:#04X}",
+                             hit.id, hit.address, hit.pc, hit.old, hit.new);
+                    break 'update_loop Ok(());
+                }
+            }
+        }
+        if let (Some(save), Some(cartridge)) = (save_file.as_mut(), gameboy.mmu.cartridge.as_mut()) {
+            if let Err(e) = save.flush_if_due(cartridge) {
+                break 'update_loop Err(e.into());
+            }
+        }
+        if let Some(cartridge) = gameboy.mmu.cartridge.as_mut() {
+            let (was_rumbling, was_lit) = (rumble, infrared);
+            while let Some(event) = cartridge.poll_event() {
+                match event {
+                    CartridgeEvent::Rumble(motor) => rumble = motor,
+                    CartridgeEvent::Infrared(light) => infrared = light,
+                }
+            }
+            // games pulse the motor many times a frame to set its strength, only where it ends up is reported
+            if rumble != was_rumbling {
+                println!("rumble {}", if rumble { "on" } else { "off" });
+            }
+            if infrared != was_lit {
+                println!("infrared LED {}", if infrared { "on" } else { "off" });
+            }
+        }
+
+        // headless runs as fast as it can for a fixed number of cycles
+        let ppu_window = match ppu_window.as_mut() {
+            Some(ppu_window) => ppu_window,
+            None if gameboy.cpu.clocks.total > 1_000_000_000 => break 'update_loop Ok(()),
+            None => continue,
+        };
+        let is_open = if gameboy.ppu.frame_ready() {
+            ppu_window.present(&gameboy.ppu.frame)
+        } else {
+            Ok(ppu_window.update())
+        };
+        match is_open {
+            Ok(true) => {}
+            Ok(false) => break 'update_loop Ok(()),
+            Err(e) => break 'update_loop Err(e.into()),
+        }
+        gameboy.mmu.set_buttons(ppu_window.buttons());
+        for hotkey in ppu_window.pressed_hotkeys() {
+            match hotkey {
+                Hotkey::Pause => is_paused = !is_paused,
+                // pauses first if running, then steps a frame per press
+                Hotkey::FrameAdvance => {
+                    frame_advance = is_paused;
+                    is_paused = true;
+                }
+                Hotkey::SlowMotion => is_slow_motion = !is_slow_motion,
+                Hotkey::Screenshot => {
+                    let path = screenshot_path();
+                    let written = File::create(&path)
+                        .and_then(|file| gameboy.ppu.frame.write_ppm(ppu_window.palette(), file));
+                    // not worth ending the session over, the directory may just be read-only
+                    match written {
+                        Ok(()) => println!("screenshot saved to {}", path.display()),
+                        Err(e) => eprintln!("couldn't save screenshot to {}: {}", path.display(), e),
+                    }
+                }
+                Hotkey::FastForward => {}
+            }
+        }
+        pacer.set_speed(if ppu_window.is_hotkey_down(Hotkey::FastForward) && !is_paused {
+            turbo
+        } else if is_slow_motion {
+            slow_motion
+        } else {
+            Speed::default()
+        });
+        pacer.wait_for_next_frame();
+    };
+
+    let flushed = match (save_file.as_mut(), gameboy.mmu.cartridge.as_mut()) {
+        (Some(save), Some(cartridge)) => save.flush(cartridge),
+        _ => Ok(()),
+    };
+    result?;
+    flushed?;
+
     // for _ in 0..1_000_000 {
     //     println!("{:?}", cpu);
     //     cpu.execute(&mut mmu);
     // }
     Ok(())
-}
\ No newline at end of file
+}
+
+/// First of screenshot-1.ppm, screenshot-2.ppm, ... that doesn't exist yet
+fn screenshot_path() -> PathBuf {
+    (1..).map(|n| PathBuf::from(format!("screenshot-{}.ppm", n)))
+        .find(|path| !path.exists())
+        .unwrap_or_default()
+}
@@ -0,0 +1,98 @@
+use std::time::{Duration, Instant};
+
+/// T-cycles per second and per frame, a frame is 154 lines of 456 dots
+pub const CLOCK_HZ: u64 = 4_194_304;
+pub const CYCLES_PER_FRAME: u64 = 70_224;
+/// Sleeping is only trusted up to this close to a deadline, the rest is spun away
+const SPIN_MARGIN: Duration = Duration::from_millis(2);
+/// Further behind than this (a debugger break, a dragged window) and the pacer starts over instead of racing to
+/// catch up
+const MAX_LAG_FRAMES: u32 = 4;
+
+/// How fast emulated time runs against real time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Speed {
+    /// As fast as the host can go
+    Uncapped,
+    /// 1.0 is the real 59.73 Hz, 4.0 is a 4x turbo, 0.25 slow motion
+    Times(f64),
+}
+
+impl Default for Speed {
+    fn default() -> Speed {
+        Speed::Times(1.0)
+    }
+}
+
+impl Speed {
+    /// Real time one emulated frame gets, none when uncapped
+    pub fn frame_duration(&self) -> Option<Duration> {
+        match *self {
+            Speed::Uncapped => None,
+            Speed::Times(factor) => {
+                Some(Duration::from_secs_f64(CYCLES_PER_FRAME as f64 / CLOCK_HZ as f64 / factor))
+            }
+        }
+    }
+}
+
+/// Whatever the frontend waits on between frames. Audio output will be another one: blocking until its queue drains
+/// below a frame's worth of samples keeps the sound gapless and paces the video for free.
+pub trait FrameSync {
+    /// Called once an emulated frame is done, returns when the next one may start
+    fn wait_for_next_frame(&mut self);
+
+    fn set_speed(&mut self, speed: Speed);
+}
+
+/// Wall clock pacing. Deadlines are absolute, each one is the previous plus a frame, so oversleeping one frame is made
+/// up on the next and the rate doesn't drift. The OS sleep gets within `SPIN_MARGIN` and a spin does the rest.
+#[derive(Debug)]
+pub struct FramePacer {
+    speed: Speed,
+    deadline: Instant,
+}
+
+impl Default for FramePacer {
+    fn default() -> FramePacer {
+        FramePacer { speed: Speed::default(), deadline: Instant::now() }
+    }
+}
+
+impl FramePacer {
+    pub fn new(speed: Speed) -> FramePacer {
+        FramePacer { speed, deadline: Instant::now() }
+    }
+
+    pub fn speed(&self) -> Speed {
+        self.speed
+    }
+}
+
+impl FrameSync for FramePacer {
+    fn wait_for_next_frame(&mut self) {
+        let frame = match self.speed.frame_duration() {
+            Some(frame) => frame,
+            None => return,
+        };
+        self.deadline += frame;
+        let now = Instant::now();
+        if now > self.deadline + frame * MAX_LAG_FRAMES {
+            self.deadline = now;
+            return;
+        }
+        if let Some(sleep) = self.deadline.checked_duration_since(now + SPIN_MARGIN) {
+            std::thread::sleep(sleep);
+        }
+        while Instant::now() < self.deadline {
+            std::hint::spin_loop();
+        }
+    }
+
+    fn set_speed(&mut self, speed: Speed) {
+        if speed != self.speed {
+            self.speed = speed;
+            self.deadline = Instant::now();
+        }
+    }
+}
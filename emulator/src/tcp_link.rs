@@ -0,0 +1,168 @@
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use memory_bus::serial::SerialEndpoint;
+
+/// Every frame is a kind byte, the sender's M-cycle count (little endian) and the serial byte
+const FRAME_SIZE: usize = 10;
+/// Sent once when a side starts waiting for the external clock, carries the byte it will shift out
+const FRAME_ARMED: u8 = 0x01;
+/// Sent by the master when its transfer completes, carries the byte it shifted out
+const FRAME_DATA: u8 = 0x02;
+
+#[derive(Debug, Clone, Copy)]
+pub struct TcpLinkConfig {
+    /// How long a master blocks waiting to learn what the other side has loaded, after that it reads 0xFF
+    pub latency: Duration,
+    /// How many M-cycles a DATA frame may arrive after the cycle it was sent on. There's no rollback, a later frame is
+    /// still delivered, but the peer's clock is shifted onto ours so the bytes after it keep their spacing.
+    pub max_skew: u64,
+}
+
+impl Default for TcpLinkConfig {
+    fn default() -> TcpLinkConfig {
+        TcpLinkConfig { latency: Duration::from_millis(50), max_skew: 1024 }
+    }
+}
+
+/// Link cable to another emulator process. Cycle timestamps count from when the connection was made, a received byte
+/// is held back until the local cycle count catches up with the sender's, so the faster side doesn't see it early.
+#[derive(Debug)]
+pub struct TcpLinkEndpoint {
+    stream: TcpStream,
+    config: TcpLinkConfig,
+    cycle: u64,
+    received: Vec<u8>,
+    // byte the peer is waiting to shift out on our clock
+    peer_armed: Option<u8>,
+    // byte we told the peer we're waiting with
+    armed: Option<u8>,
+    // byte from the peer's clock and the cycle it was sent on
+    incoming: Option<(u64, u8)>,
+    // added to the peer's timestamps, grows every time it falls more than `max_skew` behind
+    peer_offset: u64,
+    late_frames: usize,
+    disconnected: bool,
+}
+
+impl TcpLinkEndpoint {
+    /// Waits for the other process to connect on `port` of localhost
+    pub fn listen(port: u16, config: TcpLinkConfig) -> io::Result<TcpLinkEndpoint> {
+        let (stream, _) = TcpListener::bind(("127.0.0.1", port))?.accept()?;
+        TcpLinkEndpoint::from_stream(stream, config)
+    }
+
+    pub fn connect<A: ToSocketAddrs>(addr: A, config: TcpLinkConfig) -> io::Result<TcpLinkEndpoint> {
+        TcpLinkEndpoint::from_stream(TcpStream::connect(addr)?, config)
+    }
+
+    pub fn from_stream(stream: TcpStream, config: TcpLinkConfig) -> io::Result<TcpLinkEndpoint> {
+        stream.set_nodelay(true)?;
+        stream.set_nonblocking(true)?;
+        Ok(TcpLinkEndpoint {
+            stream,
+            config,
+            cycle: 0,
+            received: Vec::new(),
+            peer_armed: None,
+            armed: None,
+            incoming: None,
+            peer_offset: 0,
+            late_frames: 0,
+            disconnected: false,
+        })
+    }
+
+    /// DATA frames that arrived more than `max_skew` M-cycles after they were sent, each one resynced the clocks
+    pub fn late_frames(&self) -> usize {
+        self.late_frames
+    }
+
+    pub fn is_disconnected(&self) -> bool {
+        self.disconnected
+    }
+
+    fn send(&mut self, kind: u8, byte: u8) {
+        let mut frame = [0; FRAME_SIZE];
+        frame[0] = kind;
+        frame[1..9].copy_from_slice(&self.cycle.to_le_bytes());
+        frame[9] = byte;
+        // the socket is non-blocking, but a 10 byte frame only fails to go out whole if the peer stopped reading
+        if self.stream.write_all(&frame).is_err() {
+            self.disconnected = true;
+        }
+    }
+
+    /// Reads whatever arrived without blocking and applies every complete frame
+    fn receive(&mut self) {
+        let mut buffer = [0; 256];
+        while !self.disconnected {
+            match self.stream.read(&mut buffer) {
+                Ok(0) => self.disconnected = true,
+                Ok(n) => self.received.extend_from_slice(&buffer[..n]),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(_) => self.disconnected = true,
+            }
+        }
+        let frames = self.received.len() / FRAME_SIZE;
+        for frame in self.received.drain(..frames * FRAME_SIZE).collect::<Vec<_>>().chunks(FRAME_SIZE) {
+            let mut cycle = [0; 8];
+            cycle.copy_from_slice(&frame[1..9]);
+            // saturating, the timestamp comes straight off the wire
+            let cycle = u64::from_le_bytes(cycle).saturating_add(self.peer_offset);
+            match frame[0] {
+                FRAME_ARMED => self.peer_armed = Some(frame[9]),
+                FRAME_DATA => {
+                    let skew = self.cycle.saturating_sub(cycle);
+                    if skew > self.config.max_skew {
+                        self.peer_offset = self.peer_offset.saturating_add(skew);
+                        self.late_frames += 1;
+                    }
+                    self.incoming = Some((cycle, frame[9]));
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl SerialEndpoint for TcpLinkEndpoint {
+    fn exchange(&mut self, byte: u8) -> u8 {
+        let deadline = Instant::now() + self.config.latency;
+        self.receive();
+        while self.peer_armed.is_none() && !self.disconnected && Instant::now() < deadline {
+            thread::sleep(Duration::from_micros(100));
+            self.receive();
+        }
+        match self.peer_armed.take() {
+            Some(received) => {
+                self.send(FRAME_DATA, byte);
+                received
+            }
+            None => 0xFF,
+        }
+    }
+
+    fn poll_external(&mut self, byte: u8) -> Option<u8> {
+        if self.armed != Some(byte) {
+            self.armed = Some(byte);
+            self.send(FRAME_ARMED, byte);
+        }
+        self.receive();
+        match self.incoming {
+            Some((cycle, received)) if cycle <= self.cycle => {
+                self.incoming = None;
+                self.armed = None;
+                Some(received)
+            }
+            _ => None,
+        }
+    }
+
+    fn tick(&mut self) {
+        self.cycle += 1;
+    }
+}
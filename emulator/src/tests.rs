@@ -0,0 +1,170 @@
+use super::*;
+use link::Link;
+use pacing::{FramePacer, FrameSync, Speed};
+use tcp_link::{TcpLinkConfig, TcpLinkEndpoint};
+use memory_bus::io;
+use memory_bus::interrupts::Interrupt;
+use memory_bus::MMU;
+
+fn prerequisites() -> Link {
+    // no cartridge, both CPUs run NOPs out of flat RAM
+    Link::new(Gameboy::new(MMU::new()), Gameboy::new(MMU::new()))
+}
+
+#[test]
+fn link_transfer_test() {
+    let mut link = prerequisites();
+    link.right.mmu.write_byte(io::SB, 0x99);
+    link.right.mmu.write_byte(io::SC, 0x80); // slave, waits for the clock
+    link.left.mmu.write_byte(io::SB, 0x42);
+    link.left.mmu.write_byte(io::SC, 0x81); // master
+    for _ in 0..1023 {
+        link.step();
+    }
+    assert!(link.left.mmu.serial.is_transferring());
+    assert!(link.right.mmu.serial.is_transferring());
+    link.step();
+    assert_eq!(link.left.mmu.read_byte(io::SB), 0x99);
+    assert_eq!(link.right.mmu.read_byte(io::SB), 0x42);
+    assert!(link.left.mmu.interrupts.is_requested(Interrupt::Serial));
+    assert!(link.right.mmu.interrupts.is_requested(Interrupt::Serial));
+
+    // the other direction, and a master with nobody listening
+    link.left.mmu.write_byte(io::SB, 0x11);
+    link.left.mmu.write_byte(io::SC, 0x80);
+    link.right.mmu.write_byte(io::SB, 0x22);
+    link.right.mmu.write_byte(io::SC, 0x81);
+    for _ in 0..1025 {
+        link.step();
+    }
+    assert_eq!(link.left.mmu.read_byte(io::SB), 0x22);
+    assert_eq!(link.right.mmu.read_byte(io::SB), 0x11);
+    link.right.mmu.write_byte(io::SC, 0x81);
+    for _ in 0..1024 {
+        link.step();
+    }
+    assert_eq!(link.right.mmu.read_byte(io::SB), 0xFF);
+}
+
+#[test]
+fn tcp_link_test() {
+    use std::net::{TcpListener, TcpStream};
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let connection = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let (accepted, _) = listener.accept().unwrap();
+    // shares the socket with the right side's endpoint, peeking tells when a frame has landed without taking it
+    let probe = accepted.try_clone().unwrap();
+    let config = TcpLinkConfig::default();
+    let mut left = Gameboy::new(MMU::new());
+    let mut right = Gameboy::new(MMU::new());
+    left.mmu.serial.connect(Box::new(TcpLinkEndpoint::from_stream(connection, config).unwrap()));
+    right.mmu.serial.connect(Box::new(TcpLinkEndpoint::from_stream(accepted, config).unwrap()));
+
+    right.mmu.write_byte(io::SB, 0x99);
+    right.mmu.write_byte(io::SC, 0x80);
+    right.step(); // announces what it's waiting with
+    left.mmu.write_byte(io::SB, 0x42);
+    left.mmu.write_byte(io::SC, 0x81);
+    for _ in 0..1024 {
+        left.step();
+    }
+    assert_eq!(left.mmu.read_byte(io::SB), 0x99);
+    assert!(left.mmu.interrupts.is_requested(Interrupt::Serial));
+
+    // wait for the DATA frame itself, however long loopback takes on a busy machine
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+    while probe.peek(&mut [0; 10]).map_or(true, |received| received < 10) {
+        assert!(std::time::Instant::now() < deadline, "DATA frame never arrived");
+        std::thread::sleep(std::time::Duration::from_millis(1));
+    }
+
+    // the byte was sent on cycle 1024, it is already here but isn't handed over before the right side gets there
+    for _ in 0..1022 {
+        right.step();
+    }
+    assert!(right.mmu.serial.is_transferring());
+    right.step();
+    assert_eq!(right.mmu.read_byte(io::SB), 0x42);
+    assert!(right.mmu.interrupts.is_requested(Interrupt::Serial));
+}
+
+#[test]
+fn tcp_link_skew_test() {
+    use std::io::Write;
+    use std::net::{TcpListener, TcpStream};
+    use memory_bus::serial::SerialEndpoint;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let mut peer = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let (accepted, _) = listener.accept().unwrap();
+    let probe = accepted.try_clone().unwrap();
+    let config = TcpLinkConfig { max_skew: 100, ..TcpLinkConfig::default() };
+    let mut endpoint = TcpLinkEndpoint::from_stream(accepted, config).unwrap();
+    let mut send_data = |cycle: u64, byte: u8| {
+        let mut frame = [0x02; 10];
+        frame[1..9].copy_from_slice(&cycle.to_le_bytes());
+        frame[9] = byte;
+        peer.write_all(&frame).unwrap();
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while probe.peek(&mut [0; 10]).map_or(true, |received| received < 10) {
+            assert!(std::time::Instant::now() < deadline, "DATA frame never arrived");
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+    };
+
+    // 1000 M-cycles late, delivered anyway and the peer's clock moves up by the difference
+    for _ in 0..1010 {
+        endpoint.tick();
+    }
+    send_data(10, 0x42);
+    assert_eq!(endpoint.poll_external(0x99), Some(0x42));
+    assert_eq!(endpoint.late_frames(), 1);
+
+    // so the next byte, sent 50 M-cycles later on the peer's side, arrives 50 M-cycles later on ours too
+    send_data(60, 0x43);
+    for _ in 0..50 {
+        assert_eq!(endpoint.poll_external(0x99), None);
+        endpoint.tick();
+    }
+    assert_eq!(endpoint.poll_external(0x99), Some(0x43));
+    assert_eq!(endpoint.late_frames(), 1);
+
+    // a garbage timestamp is held back forever instead of overflowing
+    send_data(u64::MAX, 0x44);
+    assert_eq!(endpoint.poll_external(0x99), None);
+    assert!(!endpoint.is_disconnected());
+}
+
+#[test]
+fn pacing_test() {
+    use std::time::{Duration, Instant};
+
+    assert_eq!(Speed::default().frame_duration(), Some(Duration::from_nanos(16_742_706)));
+    assert_eq!(Speed::Times(2.0).frame_duration(), Some(Duration::from_nanos(8_371_353)));
+    assert_eq!(Speed::Uncapped.frame_duration(), None);
+
+    // deadlines are absolute, so a late frame is made up by the next one
+    let mut pacer = FramePacer::new(Speed::Times(4.0));
+    let start = Instant::now();
+    pacer.wait_for_next_frame();
+    std::thread::sleep(Duration::from_millis(6));
+    pacer.wait_for_next_frame();
+    let elapsed = start.elapsed();
+    assert!(elapsed >= Duration::from_nanos(2 * 4_185_676), "{:?}", elapsed);
+    assert!(elapsed < Duration::from_millis(9) + Duration::from_millis(50), "{:?}", elapsed);
+
+    // far behind, it starts over rather than rushing through the missed frames
+    std::thread::sleep(Duration::from_millis(50));
+    let start = Instant::now();
+    pacer.wait_for_next_frame();
+    pacer.wait_for_next_frame();
+    assert!(start.elapsed() >= Duration::from_nanos(4_185_676));
+
+    pacer.set_speed(Speed::Uncapped);
+    let start = Instant::now();
+    for _ in 0..100 {
+        pacer.wait_for_next_frame();
+    }
+    assert!(start.elapsed() < Duration::from_millis(50));
+}
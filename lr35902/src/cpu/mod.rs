@@ -82,6 +82,20 @@ impl LR35902 {
         }
     }
 
+    /// Puts registers into the state the DMG boot ROM leaves them in, for running a cartridge without one
+    pub fn skip_boot_rom(&mut self) {
+        self.a = 0x01;
+        self.f = 0xB0;
+        self.b = 0x00;
+        self.c = 0x13;
+        self.d = 0x00;
+        self.e = 0xD8;
+        self.h = 0x01;
+        self.l = 0x4D;
+        self.sp = 0xFFFE;
+        self.pc = 0x0100;
+    }
+
     pub fn set_flag(&mut self, flag: Flag) {
         self.f |= 1 << (flag as u8);
     }
@@ -101,23 +115,24 @@ impl LR35902 {
     }
 
     pub fn push(&mut self, mmu: &mut MMU, value: u16) {
-        self.sp -= 2;
-        mmu.write_byte((self.sp) as usize, value as u8);
-        mmu.write_byte((self.sp + 1) as usize, (value >> 8) as u8);
+        self.sp = self.sp.wrapping_sub(2);
+        mmu.write_byte(self.sp, value as u8);
+        mmu.write_byte(self.sp.wrapping_add(1), (value >> 8) as u8);
         
     }
 
     pub fn pop(&mut self, mmu: &MMU) -> u16 {
-        let l = mmu.read_byte((self.sp) as usize);
-        let h = mmu.read_byte((self.sp + 1) as usize);
-        self.sp += 2;
+        let l = mmu.read_byte(self.sp);
+        let h = mmu.read_byte(self.sp.wrapping_add(1));
+        self.sp = self.sp.wrapping_add(2);
         ((h as u16) << 8) | l as u16
     }
 
     #[cfg(feature = "instruction_table")]
     pub fn init(&mut self, mmu: &mut MMU) {
         use instructions::INS_TABLE;
-        self.bytes = mmu.read_ahead(self.pc as usize);
+        mmu.watch_execute(self.pc);
+        self.bytes = mmu.read_ahead(self.pc);
         self.opcode = self.bytes[0];
         self.next_operation = INS_TABLE[self.opcode as usize];
     }
@@ -129,8 +144,10 @@ impl LR35902 {
         self.clocks.add(clocks);
         if self.clocks.current >= self.pending_clocks {
             self.clocks.current -= self.pending_clocks;
+            mmu.set_pc(self.pc);
             self.execute(mmu, self.next_operation.handler);
-            self.bytes = mmu.read_ahead(self.pc as usize);
+            mmu.watch_execute(self.pc);
+            self.bytes = mmu.read_ahead(self.pc);
             self.opcode = self.bytes[0];
             if self.opcode == 0xCB {
                 self.next_operation = CB_INS_TABLE[self.bytes[1] as usize];
@@ -213,12 +230,15 @@ macro_rules! g { // short for generate
                 let mut half_carry: bool = false;
 
                 // cpu.clocks.add($timing);
-                cpu.pc += $inst_size;
+                cpu.pc = cpu.pc.wrapping_add($inst_size);
 
                 if $opcode == 0x76 {
                     cpu.halted = true;
                     return;
                 }
+                if $opcode == 0x10 {
+                    mmu.cgb.perform_speed_switch();
+                }
 
                 macro_rules! expand {
                 ($operation:expr, $code:block) => {
@@ -290,8 +310,8 @@ macro_rules! g { // short for generate
                 // OP_COMPLEMENT
                 //expand!(OP_EXAMPLE)
                 expand!(OP_8BIT_AA, { t_abs_addr |= 0xFF00; } );
-                expand!(OP_SRC_R_AA, { t_src = mmu.read_byte(t_abs_addr as usize) as u16; });
-                expand!(OP_DST_R_AA, { t_dst = mmu.read_byte(t_abs_addr as usize) as u16; });
+                expand!(OP_SRC_R_AA, { t_src = mmu.read_byte(t_abs_addr) as u16; });
+                expand!(OP_DST_R_AA, { t_dst = mmu.read_byte(t_abs_addr) as u16; });
                 // operations
                 // should write to t_result generally, where we can write it however we want
 
@@ -350,8 +370,8 @@ macro_rules! g { // short for generate
                 expand!(OP_PUSH, { cpu.push(mmu, t_result); });
 
                 expand!(OP_JUMP, { cpu.pc = t_abs_addr as u16; });
-                expand!(OP_RES_W_ADDR_16, { mmu.write_word(t_abs_addr as usize, t_result); println!("16 bit write to {} {}", t_abs_addr, t_result);});
-                expand!(OP_RES_W_ADDR, { mmu.write_byte(t_abs_addr as usize, t_result as u8); });
+                expand!(OP_RES_W_ADDR_16, { mmu.write_word(t_abs_addr, t_result); println!("16 bit write to {} {}", t_abs_addr, t_result);});
+                expand!(OP_RES_W_ADDR, { mmu.write_byte(t_abs_addr, t_result as u8); });
                 expand!(OP_RES_W_RA, { cpu.a = t_result as u8; });
                 expand!(OP_RES_W_RB, { cpu.b = t_result as u8; });
                 expand!(OP_RES_W_RC, { cpu.c = t_result as u8; });
@@ -427,7 +447,7 @@ macro_rules! cb_g {
             let mut variety: u8 = variety_cb($opcode);
 
             // cpu.clocks.add($timing);
-            cpu.pc += $inst_size;
+            cpu.pc = cpu.pc.wrapping_add($inst_size);
 
             macro_rules! expand {
                ($operation:expr, $code:block) => {
@@ -443,7 +463,7 @@ macro_rules! cb_g {
             expand!(&CBOP_SRC_R_RE, { t_src = cpu.e as u16;});
             expand!(&CBOP_SRC_R_RH, { t_src = cpu.h as u16;});
             expand!(&CBOP_SRC_R_RL, { t_src = cpu.l as u16;});
-            expand!(&CBOP_SRC_R_AHL, { t_src = mmu.read_byte(((cpu.h as usize) << 8) | cpu.l as usize)  as u16; });
+            expand!(&CBOP_SRC_R_AHL, { t_src = mmu.read_byte(((cpu.h as u16) << 8) | cpu.l as u16)  as u16; });
             expand!(&CBOP_BIT, {
                 let t = (t_src & (0x1 << variety)) > 0;
                 if !t {
@@ -12,6 +12,26 @@ fn nop_test() {
     assert_eq!(cpu.pc, 1);
 }
 
+#[test]
+fn pc_wraps_test() {
+    // like the bus, execution runs off 0xFFFF into 0x0000 instead of overflowing
+    let (mut cpu, mut mmu) = prerequisites();
+    g!(f0, 0x00, 1, 4); // nop
+    cpu.pc = 0xFFFF;
+    (f0.handler)(&mut cpu, &mut mmu, [0x0, 0x0, 0x0, 0x0]);
+    assert_eq!(cpu.pc, 0);
+
+    g!(ld_bc_d16, 0x01, 3, 12);
+    cpu.pc = 0xFFFE;
+    (ld_bc_d16.handler)(&mut cpu, &mut mmu, [0x0, 0x01, 0x30, 0x0]);
+    assert_eq!(cpu.pc, 0x0001);
+
+    cb_g!(rlc_b, 0x00, 2, 8);
+    cpu.pc = 0xFFFF;
+    (rlc_b.handler)(&mut cpu, &mut mmu, [0xCB, 0x00, 0x0, 0x0]);
+    assert_eq!(cpu.pc, 0x0001);
+}
+
 #[test]
 fn ld_test() {
     let (mut cpu, mut mmu) = prerequisites();
@@ -516,14 +536,14 @@ fn ld_test() {
     assert_eq!(cpu.a, 0x5);
 
     g!(ld_ac_a, 0xE2, 2, 12);
-    cpu.c = 0x11;
+    cpu.c = 0x81;
     cpu.a = 0x15;
     (ld_ac_a.handler)(&mut cpu, &mut mmu, [0x0, 0xF1, 0x0, 0x0]);
-    assert_eq!(mmu.read_byte(0xFF11), 0x15);
+    assert_eq!(mmu.read_byte(0xFF81), 0x15);
 
     g!(ld_a_ac, 0xF2, 2, 12);
-    cpu.c = 0x66;
-    mmu.write_byte(0xFF66, 0x55);
+    cpu.c = 0x86;
+    mmu.write_byte(0xFF86, 0x55);
     (ld_a_ac.handler)(&mut cpu, &mut mmu, [0x0, 0xF1, 0x0, 0x0]);
     assert_eq!(cpu.a, 0x55);
 
@@ -928,9 +948,9 @@ fn call_ret_test() {
     g!(call_a16, 0xCD, 3, 24);
     (call_a16.handler)(&mut cpu, &mut mmu, [0x0, 0x50, 0x10, 0x0]); // call 0x1050
     //current pc should be 0x03, therefore
-    //assert_eq!(mmu.read_byte(0xFFFE as usize), 0x03);
-    //assert_eq!(mmu.read_byte(0xFFFF as usize), 0x03);
-    assert_eq!(mmu.read_byte(0xFFFD as usize), 0x03);
+    //assert_eq!(mmu.read_byte(0xFFFE), 0x03);
+    //assert_eq!(mmu.read_byte(0xFFFF), 0x03);
+    assert_eq!(mmu.read_byte(0xFFFD), 0x03);
     
     assert_eq!(cpu.sp, 0xFFFD);
     assert_eq!(cpu.pc, 0x1050);
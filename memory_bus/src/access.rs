@@ -0,0 +1,30 @@
+use crate::dma::{OAM_SIZE, OAM_START};
+use crate::vram::{VRAM_END, VRAM_START};
+
+/// What the PPU is doing right now, the discriminants match STAT bits 0-1
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub enum PPUMode {
+    HBlank = 0,
+    VBlank = 1,
+    #[default]
+    ScanlineOAM = 2,
+    ScanlineVRAM = 3,
+}
+
+/// Strict follows hardware and hides VRAM and OAM from the CPU while the PPU uses them. Lenient never locks anything,
+/// which keeps games with sloppy timing (and emulator timing bugs) playable.
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub enum Accuracy {
+    #[default]
+    Strict,
+    Lenient,
+}
+
+/// Whether the CPU is locked out of `at` in the given mode: VRAM during mode 3, OAM during modes 2 and 3
+pub fn is_locked(mode: PPUMode, at: u16) -> bool {
+    match mode {
+        PPUMode::ScanlineVRAM => (VRAM_START..=VRAM_END).contains(&at) || (OAM_START..OAM_START + OAM_SIZE).contains(&at),
+        PPUMode::ScanlineOAM => (OAM_START..OAM_START + OAM_SIZE).contains(&at),
+        PPUMode::HBlank | PPUMode::VBlank => false,
+    }
+}
@@ -0,0 +1,84 @@
+use super::{CartridgeEvent, Mapper, RAM_BANK_SIZE, ROM_BANK_SIZE};
+
+/// Hudson's MBC1 lookalike. Writing 0x0E to the RAM enable register swaps 0xA000-0xBFFF over to the infrared port.
+#[derive(Debug)]
+pub struct HuC1 {
+    rom_bank: usize,
+    ram_bank: usize,
+    ir_mode: bool,
+    ir_led: bool,
+    ir_light: bool,
+}
+
+impl Default for HuC1 {
+    fn default() -> HuC1 {
+        HuC1 {
+            rom_bank: 1,
+            ram_bank: 0,
+            ir_mode: false,
+            ir_led: false,
+            ir_light: false,
+        }
+    }
+}
+
+impl Mapper for HuC1 {
+    fn write_register(&mut self, at: u16, byte: u8) -> Option<CartridgeEvent> {
+        match at {
+            0x0000..=0x1FFF => self.ir_mode = byte == 0x0E,
+            0x2000..=0x3FFF => self.rom_bank = (byte & 0x3F) as usize,
+            0x4000..=0x5FFF => self.ram_bank = (byte & 0x03) as usize,
+            _ => {}
+        }
+        None
+    }
+
+    fn rom_offset(&self, at: u16) -> usize {
+        let at = at as usize;
+        if at < ROM_BANK_SIZE {
+            at
+        } else {
+            self.rom_bank * ROM_BANK_SIZE + (at & (ROM_BANK_SIZE - 1))
+        }
+    }
+
+    fn ram_offset(&self, at: u16) -> Option<usize> {
+        if self.ir_mode {
+            None
+        } else {
+            Some(self.ram_bank * RAM_BANK_SIZE + ((at as usize) & (RAM_BANK_SIZE - 1)))
+        }
+    }
+
+    fn read_ram(&self, ram: &[u8], at: u16) -> u8 {
+        if self.ir_mode {
+            return 0xC0 | self.ir_light as u8;
+        }
+        match self.ram_offset(at) {
+            Some(offset) if !ram.is_empty() => ram[offset % ram.len()],
+            _ => 0xFF,
+        }
+    }
+
+    fn write_ram(&mut self, ram: &mut [u8], at: u16, byte: u8) -> Option<CartridgeEvent> {
+        if self.ir_mode {
+            let led = byte & 0x01 > 0;
+            if led != self.ir_led {
+                self.ir_led = led;
+                return Some(CartridgeEvent::Infrared(led));
+            }
+            return None;
+        }
+        if let Some(offset) = self.ram_offset(at) {
+            if !ram.is_empty() {
+                let len = ram.len();
+                ram[offset % len] = byte;
+            }
+        }
+        None
+    }
+
+    fn receive_infrared(&mut self, light: bool) {
+        self.ir_light = light;
+    }
+}
@@ -0,0 +1,57 @@
+use super::{CartridgeEvent, Mapper, RAM_BANK_SIZE, ROM_BANK_SIZE};
+
+/// 5 bit ROM bank number plus a 2 bit register that either extends it (up to 2MiB of ROM) or picks one of 4 RAM
+/// banks. In mode 1 the 2 bit register also banks 0x0000-0x3FFF and RAM, in mode 0 those stay on bank 0.
+#[derive(Debug)]
+pub struct MBC1 {
+    rom_bank: usize,
+    upper_bank: usize,
+    advanced_mode: bool,
+    ram_enabled: bool,
+}
+
+impl Default for MBC1 {
+    fn default() -> MBC1 {
+        MBC1 {
+            rom_bank: 1,
+            upper_bank: 0,
+            advanced_mode: false,
+            ram_enabled: false,
+        }
+    }
+}
+
+impl Mapper for MBC1 {
+    fn write_register(&mut self, at: u16, byte: u8) -> Option<CartridgeEvent> {
+        match at {
+            0x0000..=0x1FFF => self.ram_enabled = byte & 0x0F == 0x0A,
+            // only the low 5 bits are checked for 0, which is why banks 0x20, 0x40 and 0x60 can't be mapped here
+            0x2000..=0x3FFF => self.rom_bank = match byte & 0x1F {
+                0 => 1,
+                bank => bank as usize,
+            },
+            0x4000..=0x5FFF => self.upper_bank = (byte & 0x03) as usize,
+            0x6000..=0x7FFF => self.advanced_mode = byte & 0x01 > 0,
+            _ => {}
+        }
+        None
+    }
+
+    fn rom_offset(&self, at: u16) -> usize {
+        let at = at as usize;
+        if at < ROM_BANK_SIZE {
+            let bank = if self.advanced_mode { self.upper_bank << 5 } else { 0 };
+            bank * ROM_BANK_SIZE + at
+        } else {
+            (self.upper_bank << 5 | self.rom_bank) * ROM_BANK_SIZE + (at & (ROM_BANK_SIZE - 1))
+        }
+    }
+
+    fn ram_offset(&self, at: u16) -> Option<usize> {
+        if !self.ram_enabled {
+            return None;
+        }
+        let bank = if self.advanced_mode { self.upper_bank } else { 0 };
+        Some(bank * RAM_BANK_SIZE + ((at as usize) & (RAM_BANK_SIZE - 1)))
+    }
+}
@@ -0,0 +1,69 @@
+use super::{CartridgeEvent, Mapper, ROM_BANK_SIZE};
+
+/// 512 half-bytes built into the controller itself
+pub(super) const RAM_SIZE: usize = 0x200;
+
+/// Up to 16 ROM banks and built-in 512x4 bit RAM. Both registers live in 0x0000-0x3FFF, address bit 8 selects which one is written.
+#[derive(Debug)]
+pub struct MBC2 {
+    rom_bank: usize,
+    ram_enabled: bool,
+}
+
+impl Default for MBC2 {
+    fn default() -> MBC2 {
+        MBC2 {
+            rom_bank: 1,
+            ram_enabled: false,
+        }
+    }
+}
+
+impl Mapper for MBC2 {
+    fn write_register(&mut self, at: u16, byte: u8) -> Option<CartridgeEvent> {
+        if at < 0x4000 {
+            if at & 0x100 == 0 {
+                self.ram_enabled = byte & 0x0F == 0x0A;
+            } else {
+                self.rom_bank = match byte & 0x0F {
+                    0 => 1,
+                    bank => bank as usize,
+                };
+            }
+        }
+        None
+    }
+
+    fn rom_offset(&self, at: u16) -> usize {
+        let at = at as usize;
+        if at < ROM_BANK_SIZE {
+            at
+        } else {
+            self.rom_bank * ROM_BANK_SIZE + (at & (ROM_BANK_SIZE - 1))
+        }
+    }
+
+    fn ram_offset(&self, at: u16) -> Option<usize> {
+        if self.ram_enabled {
+            Some((at as usize) & (RAM_SIZE - 1)) // echoed all over 0xA000-0xBFFF
+        } else {
+            None
+        }
+    }
+
+    fn read_ram(&self, ram: &[u8], at: u16) -> u8 {
+        match self.ram_offset(at) {
+            Some(offset) if offset < ram.len() => 0xF0 | ram[offset], // upper nibble isn't wired
+            _ => 0xFF,
+        }
+    }
+
+    fn write_ram(&mut self, ram: &mut [u8], at: u16, byte: u8) -> Option<CartridgeEvent> {
+        if let Some(offset) = self.ram_offset(at) {
+            if offset < ram.len() {
+                ram[offset] = byte & 0x0F;
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,174 @@
+use std::time::Instant;
+
+use super::{CartridgeEvent, Mapper, RAM_BANK_SIZE, ROM_BANK_SIZE};
+
+const RTC_SECONDS: u8 = 0x08;
+const RTC_DAY_HIGH: u8 = 0x0C;
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+/// The day counter is 9 bits, going past it sets the carry flag
+const DAYS: u64 = 512;
+
+/// Real time clock counting host time. Games read it through a latched copy so the registers can't roll over midway
+/// through reading them.
+#[derive(Debug)]
+struct Rtc {
+    // seconds counted up to `since`
+    seconds: u64,
+    since: Instant,
+    halted: bool,
+    carry: bool,
+    // seconds, minutes, hours, day low bits, day high bit with the halt and carry flags
+    latched: [u8; 5],
+}
+
+impl Rtc {
+    fn new() -> Rtc {
+        Rtc { seconds: 0, since: Instant::now(), halted: false, carry: false, latched: [0; 5] }
+    }
+
+    fn now(&self) -> u64 {
+        if self.halted {
+            self.seconds
+        } else {
+            self.seconds + self.since.elapsed().as_secs()
+        }
+    }
+
+    fn registers(&self) -> [u8; 5] {
+        let now = self.now();
+        let days = now / SECONDS_PER_DAY;
+        let carry = self.carry || days >= DAYS;
+        let days = days % DAYS;
+        [
+            (now % 60) as u8,
+            (now / 60 % 60) as u8,
+            (now / 3600 % 24) as u8,
+            days as u8,
+            (days >> 8) as u8 | (self.halted as u8) << 6 | (carry as u8) << 7,
+        ]
+    }
+
+    fn latch(&mut self) {
+        self.latched = self.registers();
+    }
+
+    fn write(&mut self, register: u8, byte: u8) {
+        let mut registers = self.registers();
+        registers[(register - RTC_SECONDS) as usize] = byte;
+        let [seconds, minutes, hours, day_low, day_high] = registers;
+        let days = day_low as u64 | ((day_high & 0x01) as u64) << 8;
+        self.seconds = (seconds & 0x3F) as u64 + (minutes & 0x3F) as u64 * 60 + (hours & 0x1F) as u64 * 3600
+            + days * SECONDS_PER_DAY;
+        self.since = Instant::now();
+        self.halted = day_high & 0x40 > 0;
+        self.carry = day_high & 0x80 > 0;
+    }
+}
+
+/// 7 bit ROM bank number, 4 RAM banks and on some carts a real time clock whose registers get mapped in place of RAM.
+#[derive(Debug)]
+pub struct MBC3 {
+    rom_bank: usize,
+    // 0x00-0x03 picks a RAM bank, 0x08-0x0C an RTC register
+    ram_select: u8,
+    ram_enabled: bool,
+    rtc: Option<Rtc>,
+    // latching takes a 0 then a 1 written to 0x6000-0x7FFF
+    latch_armed: bool,
+}
+
+impl MBC3 {
+    pub fn new(has_rtc: bool) -> MBC3 {
+        MBC3 {
+            rom_bank: 1,
+            ram_select: 0,
+            ram_enabled: false,
+            rtc: if has_rtc { Some(Rtc::new()) } else { None },
+            latch_armed: false,
+        }
+    }
+
+    /// The RTC register currently mapped into 0xA000-0xBFFF, if any
+    fn rtc_register(&self) -> Option<u8> {
+        match self.ram_select {
+            RTC_SECONDS..=RTC_DAY_HIGH if self.ram_enabled && self.rtc.is_some() => Some(self.ram_select),
+            _ => None,
+        }
+    }
+
+    /// Moves the clock forward as if `seconds` had passed, time doesn't pass fast enough in tests
+    #[cfg(test)]
+    pub(super) fn elapse(&mut self, seconds: u64) {
+        if let Some(rtc) = self.rtc.as_mut() {
+            if !rtc.halted {
+                rtc.seconds += seconds;
+            }
+        }
+    }
+}
+
+impl Mapper for MBC3 {
+    fn write_register(&mut self, at: u16, byte: u8) -> Option<CartridgeEvent> {
+        match at {
+            0x0000..=0x1FFF => self.ram_enabled = byte & 0x0F == 0x0A,
+            0x2000..=0x3FFF => self.rom_bank = match byte & 0x7F {
+                0 => 1,
+                bank => bank as usize,
+            },
+            0x4000..=0x5FFF => self.ram_select = byte & 0x0F,
+            0x6000..=0x7FFF => {
+                if self.latch_armed && byte == 0x01 {
+                    if let Some(rtc) = self.rtc.as_mut() {
+                        rtc.latch();
+                    }
+                }
+                self.latch_armed = byte == 0x00;
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn rom_offset(&self, at: u16) -> usize {
+        let at = at as usize;
+        if at < ROM_BANK_SIZE {
+            at
+        } else {
+            self.rom_bank * ROM_BANK_SIZE + (at & (ROM_BANK_SIZE - 1))
+        }
+    }
+
+    fn ram_offset(&self, at: u16) -> Option<usize> {
+        if self.ram_enabled && self.ram_select < 0x04 {
+            Some(self.ram_select as usize * RAM_BANK_SIZE + ((at as usize) & (RAM_BANK_SIZE - 1)))
+        } else {
+            None
+        }
+    }
+
+    fn read_ram(&self, ram: &[u8], at: u16) -> u8 {
+        if let (Some(register), Some(rtc)) = (self.rtc_register(), self.rtc.as_ref()) {
+            return rtc.latched[(register - RTC_SECONDS) as usize];
+        }
+        match self.ram_offset(at) {
+            Some(offset) if !ram.is_empty() => ram[offset % ram.len()],
+            _ => 0xFF,
+        }
+    }
+
+    fn write_ram(&mut self, ram: &mut [u8], at: u16, byte: u8) -> Option<CartridgeEvent> {
+        if let Some(register) = self.rtc_register() {
+            if let Some(rtc) = self.rtc.as_mut() {
+                rtc.write(register, byte);
+            }
+            return None;
+        }
+        if let Some(offset) = self.ram_offset(at) {
+            if !ram.is_empty() {
+                let len = ram.len();
+                ram[offset % len] = byte;
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,64 @@
+use super::{CartridgeEvent, Mapper, RAM_BANK_SIZE, ROM_BANK_SIZE};
+
+/// 9 bit ROM bank number and up to 16 RAM banks. On rumble carts bit 3 of the RAM bank register drives the motor instead.
+#[derive(Debug)]
+pub struct MBC5 {
+    rom_bank: usize,
+    ram_bank: usize,
+    ram_enabled: bool,
+    rumble: bool,
+    motor: bool,
+}
+
+impl MBC5 {
+    pub fn new(rumble: bool) -> MBC5 {
+        MBC5 {
+            rom_bank: 1,
+            ram_bank: 0,
+            ram_enabled: false,
+            rumble,
+            motor: false,
+        }
+    }
+}
+
+impl Mapper for MBC5 {
+    fn write_register(&mut self, at: u16, byte: u8) -> Option<CartridgeEvent> {
+        match at {
+            0x0000..=0x1FFF => self.ram_enabled = byte & 0x0F == 0x0A,
+            0x2000..=0x2FFF => self.rom_bank = (self.rom_bank & 0x100) | byte as usize,
+            0x3000..=0x3FFF => self.rom_bank = (self.rom_bank & 0xFF) | (((byte & 0x01) as usize) << 8),
+            0x4000..=0x5FFF => {
+                if self.rumble {
+                    self.ram_bank = (byte & 0x07) as usize;
+                    let motor = byte & 0x08 > 0;
+                    if motor != self.motor {
+                        self.motor = motor;
+                        return Some(CartridgeEvent::Rumble(motor));
+                    }
+                } else {
+                    self.ram_bank = (byte & 0x0F) as usize;
+                }
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn rom_offset(&self, at: u16) -> usize {
+        let at = at as usize;
+        if at < ROM_BANK_SIZE {
+            at
+        } else {
+            self.rom_bank * ROM_BANK_SIZE + (at & (ROM_BANK_SIZE - 1)) // bank 0 is a valid pick here, unlike MBC1
+        }
+    }
+
+    fn ram_offset(&self, at: u16) -> Option<usize> {
+        if self.ram_enabled {
+            Some(self.ram_bank * RAM_BANK_SIZE + ((at as usize) & (RAM_BANK_SIZE - 1)))
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use super::{CartridgeEvent, Mapper, RAM_BANK_SIZE, ROM_BANK_SIZE};
+
+/// Multicart controller. It boots "unmapped" with the menu in the last 32KiB of ROM visible, the menu then programs the outer bank bits and sets
+/// the map enable bit, after which the outer bits are locked and the cart behaves like an MBC1 confined to the selected game.
+#[derive(Debug)]
+pub struct MMM01 {
+    rom_banks: usize,
+    mapped: bool,
+    ram_enabled: bool,
+    rom_bank_low: usize,
+    rom_bank_mid: usize,
+    rom_bank_high: usize,
+    rom_bank_mask: usize,
+    ram_bank_low: usize,
+    ram_bank_high: usize,
+}
+
+impl MMM01 {
+    pub fn new(rom_banks: usize) -> MMM01 {
+        MMM01 {
+            rom_banks: rom_banks.max(2),
+            mapped: false,
+            ram_enabled: false,
+            rom_bank_low: 0,
+            rom_bank_mid: 0,
+            rom_bank_high: 0,
+            rom_bank_mask: 0,
+            ram_bank_low: 0,
+            ram_bank_high: 0,
+        }
+    }
+
+    fn rom_bank0(&self) -> usize {
+        if !self.mapped {
+            return self.rom_banks - 2;
+        }
+        (self.rom_bank_low & (self.rom_bank_mask << 1)) | (self.rom_bank_mid << 5) | (self.rom_bank_high << 7)
+    }
+
+    fn rom_bankx(&self) -> usize {
+        if !self.mapped {
+            return self.rom_banks - 1;
+        }
+        let mut low = self.rom_bank_low;
+        if low & !(self.rom_bank_mask << 1) & 0x1F == 0 {
+            low |= 1;
+        }
+        low | (self.rom_bank_mid << 5) | (self.rom_bank_high << 7)
+    }
+}
+
+impl Mapper for MMM01 {
+    fn write_register(&mut self, at: u16, byte: u8) -> Option<CartridgeEvent> {
+        let byte = byte as usize;
+        match at {
+            0x0000..=0x1FFF => {
+                self.ram_enabled = byte & 0x0F == 0x0A;
+                if !self.mapped {
+                    self.mapped = byte & 0x40 > 0;
+                }
+            }
+            0x2000..=0x3FFF => {
+                if self.mapped {
+                    let frozen = self.rom_bank_mask << 1;
+                    self.rom_bank_low = (self.rom_bank_low & frozen) | (byte & 0x1F & !frozen);
+                } else {
+                    self.rom_bank_low = byte & 0x1F;
+                    self.rom_bank_mid = (byte >> 5) & 0x03;
+                }
+            }
+            0x4000..=0x5FFF => {
+                self.ram_bank_low = byte & 0x03;
+                if !self.mapped {
+                    self.ram_bank_high = (byte >> 2) & 0x03;
+                    self.rom_bank_high = (byte >> 4) & 0x03;
+                }
+            }
+            0x6000..=0x7FFF if !self.mapped => self.rom_bank_mask = (byte >> 2) & 0x0F,
+            _ => {}
+        }
+        None
+    }
+
+    fn rom_offset(&self, at: u16) -> usize {
+        let at = at as usize;
+        if at < ROM_BANK_SIZE {
+            self.rom_bank0() * ROM_BANK_SIZE + at
+        } else {
+            self.rom_bankx() * ROM_BANK_SIZE + (at & (ROM_BANK_SIZE - 1))
+        }
+    }
+
+    fn ram_offset(&self, at: u16) -> Option<usize> {
+        if self.ram_enabled {
+            let bank = self.ram_bank_low | (self.ram_bank_high << 2);
+            Some(bank * RAM_BANK_SIZE + ((at as usize) & (RAM_BANK_SIZE - 1)))
+        } else {
+            None
+        }
+    }
+}
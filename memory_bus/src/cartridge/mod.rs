@@ -0,0 +1,252 @@
+use std::collections::VecDeque;
+use std::fmt;
+
+mod huc1;
+mod mbc1;
+mod mbc2;
+mod mbc3;
+mod mbc5;
+mod mmm01;
+mod save;
+
+pub use huc1::HuC1;
+pub use mbc1::MBC1;
+pub use mbc2::MBC2;
+pub use mbc3::MBC3;
+pub use mbc5::MBC5;
+pub use mmm01::MMM01;
+pub use save::SaveFile;
+
+pub const ROM_BANK_SIZE: usize = 0x4000;
+pub const RAM_BANK_SIZE: usize = 0x2000;
+
+const HEADER_END: usize = 0x150;
+const HEADER_TITLE: usize = 0x134;
+const HEADER_MANUFACTURER: usize = 0x13F;
+const HEADER_CGB_FLAG: usize = 0x143;
+const HEADER_CARTRIDGE_TYPE: usize = 0x147;
+const HEADER_ROM_SIZE: usize = 0x148;
+const HEADER_RAM_SIZE: usize = 0x149;
+
+/// Side effects of cartridge hardware that the frontend might want to observe, like the rumble motor of MBC5 carts or the infrared LED of HuC1 carts
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CartridgeEvent {
+    Rumble(bool),
+    Infrared(bool),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CartridgeError {
+    TooSmall(usize),
+    UnsupportedType(u8),
+    SaveSizeMismatch { expected: usize, actual: usize },
+}
+
+impl fmt::Display for CartridgeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CartridgeError::TooSmall(size) => write!(f, "ROM is too small to contain a header ({} bytes)", size),
+            CartridgeError::UnsupportedType(kind) => write!(f, "unsupported cartridge type {:#04X}", kind),
+            CartridgeError::SaveSizeMismatch { expected, actual } => write!(f, "save RAM should be {} bytes, got {}", expected, actual),
+        }
+    }
+}
+
+impl std::error::Error for CartridgeError {}
+
+/// Memory bank controller of a cartridge. It only translates addresses, the cartridge itself owns ROM and RAM, so mappers can be tested without any backing memory.
+pub trait Mapper {
+    /// Handles a write into 0x0000-0x7FFF, which always goes to the controller registers
+    fn write_register(&mut self, at: u16, byte: u8) -> Option<CartridgeEvent>;
+
+    /// Translates 0x0000-0x7FFF into an offset into ROM, the cartridge wraps it around the actual ROM size
+    fn rom_offset(&self, at: u16) -> usize;
+
+    /// Translates 0xA000-0xBFFF into an offset into RAM, None when RAM is disabled
+    fn ram_offset(&self, at: u16) -> Option<usize>;
+
+    fn read_ram(&self, ram: &[u8], at: u16) -> u8 {
+        match self.ram_offset(at) {
+            Some(offset) if !ram.is_empty() => ram[offset % ram.len()],
+            _ => 0xFF,
+        }
+    }
+
+    fn write_ram(&mut self, ram: &mut [u8], at: u16, byte: u8) -> Option<CartridgeEvent> {
+        if let Some(offset) = self.ram_offset(at) {
+            if !ram.is_empty() {
+                let len = ram.len();
+                ram[offset % len] = byte;
+            }
+        }
+        None
+    }
+
+    /// Light hitting the infrared sensor, only HuC1 (and HuC3) carts have one
+    fn receive_infrared(&mut self, _light: bool) {}
+}
+
+/// Plain 32KiB cartridge, optionally with a single RAM bank
+#[derive(Default, Debug)]
+pub struct RomOnly;
+
+impl Mapper for RomOnly {
+    fn write_register(&mut self, _at: u16, _byte: u8) -> Option<CartridgeEvent> {
+        None
+    }
+
+    fn rom_offset(&self, at: u16) -> usize {
+        at as usize
+    }
+
+    fn ram_offset(&self, at: u16) -> Option<usize> {
+        Some((at as usize) & (RAM_BANK_SIZE - 1))
+    }
+}
+
+pub struct Cartridge {
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+    mapper: Box<dyn Mapper>,
+    events: VecDeque<CartridgeEvent>,
+    ram_dirty: bool,
+}
+
+impl Cartridge {
+    /// Builds a cartridge, picking the mapper and RAM size from the ROM header
+    pub fn new(rom: Vec<u8>) -> Result<Cartridge, CartridgeError> {
+        if rom.len() < HEADER_END {
+            return Err(CartridgeError::TooSmall(rom.len()));
+        }
+        let rom_banks = rom.len().div_ceil(ROM_BANK_SIZE);
+        let ram_size = match rom[HEADER_RAM_SIZE] {
+            0x01 => 0x800,
+            0x02 => RAM_BANK_SIZE,
+            0x03 => RAM_BANK_SIZE * 4,
+            0x04 => RAM_BANK_SIZE * 16,
+            0x05 => RAM_BANK_SIZE * 8,
+            _ => 0,
+        };
+        let (mapper, ram_size): (Box<dyn Mapper>, usize) = match rom[HEADER_CARTRIDGE_TYPE] {
+            0x00 | 0x08 | 0x09 => (Box::new(RomOnly), ram_size),
+            0x01..=0x03 => (Box::new(MBC1::default()), ram_size),
+            0x05 | 0x06 => (Box::new(MBC2::default()), mbc2::RAM_SIZE),
+            0x0B..=0x0D => (Box::new(MMM01::new(rom_banks)), ram_size),
+            0x0F | 0x10 => (Box::new(MBC3::new(true)), ram_size),
+            0x11..=0x13 => (Box::new(MBC3::new(false)), ram_size),
+            0x19..=0x1B => (Box::new(MBC5::new(false)), ram_size),
+            0x1C..=0x1E => (Box::new(MBC5::new(true)), ram_size),
+            0xFF => (Box::new(HuC1::default()), ram_size),
+            kind => return Err(CartridgeError::UnsupportedType(kind)),
+        };
+        Ok(Cartridge::with_mapper(rom, ram_size, mapper))
+    }
+
+    pub fn with_mapper(rom: Vec<u8>, ram_size: usize, mapper: Box<dyn Mapper>) -> Cartridge {
+        Cartridge {
+            rom,
+            ram: vec![0; ram_size],
+            mapper,
+            events: VecDeque::new(),
+            ram_dirty: false,
+        }
+    }
+
+    /// Game title from the header. CGB era headers take the last byte for the CGB flag, and when the four before it
+    /// look like a manufacturer code the title is only 11 bytes. Anything that isn't printable ASCII is dropped.
+    pub fn title(&self) -> String {
+        let manufacturer = self.rom.get(HEADER_MANUFACTURER..HEADER_CGB_FLAG).unwrap_or_default();
+        let end = if !self.supports_cgb() {
+            HEADER_CGB_FLAG + 1
+        } else if manufacturer.len() == 4 && manufacturer.iter().all(u8::is_ascii_uppercase) {
+            HEADER_MANUFACTURER
+        } else {
+            HEADER_CGB_FLAG
+        };
+        let title: String = self.rom.get(HEADER_TITLE..end)
+            .unwrap_or_default()
+            .iter()
+            .take_while(|byte| **byte != 0)
+            .filter(|byte| byte.is_ascii_graphic() || **byte == b' ')
+            .map(|byte| *byte as char)
+            .collect();
+        title.trim_end().to_string()
+    }
+
+    /// Header marks the game as CGB enhanced (0x80) or CGB only (0xC0)
+    pub fn supports_cgb(&self) -> bool {
+        self.rom.get(HEADER_CGB_FLAG).is_some_and(|flag| flag & 0x80 > 0)
+    }
+
+    pub fn cartridge_type(&self) -> u8 {
+        self.rom.get(HEADER_CARTRIDGE_TYPE).copied().unwrap_or_default()
+    }
+
+    pub fn rom_size_code(&self) -> u8 {
+        self.rom.get(HEADER_ROM_SIZE).copied().unwrap_or_default()
+    }
+
+    /// Whether external RAM survives power off, which is what makes it worth persisting
+    pub fn has_battery(&self) -> bool {
+        matches!(self.cartridge_type(), 0x03 | 0x06 | 0x09 | 0x0D | 0x0F | 0x10 | 0x13 | 0x1B | 0x1E | 0x22 | 0xFF) && !self.ram.is_empty()
+    }
+
+    /// Raw external RAM, in the same layout other emulators use for .sav files
+    pub fn export_ram(&self) -> &[u8] {
+        &self.ram
+    }
+
+    pub fn import_ram(&mut self, data: &[u8]) -> Result<(), CartridgeError> {
+        if data.len() != self.ram.len() {
+            return Err(CartridgeError::SaveSizeMismatch { expected: self.ram.len(), actual: data.len() });
+        }
+        self.ram.copy_from_slice(data);
+        self.ram_dirty = false;
+        Ok(())
+    }
+
+    /// True when RAM was written since the last import or `mark_ram_clean`
+    pub fn is_ram_dirty(&self) -> bool {
+        self.ram_dirty
+    }
+
+    pub fn mark_ram_clean(&mut self) {
+        self.ram_dirty = false;
+    }
+
+    pub fn read_rom(&self, at: u16) -> u8 {
+        if self.rom.is_empty() {
+            return 0xFF;
+        }
+        self.rom[self.mapper.rom_offset(at) % self.rom.len()]
+    }
+
+    pub fn read_ram(&self, at: u16) -> u8 {
+        self.mapper.read_ram(&self.ram, at)
+    }
+
+    pub fn write_rom(&mut self, at: u16, byte: u8) {
+        if let Some(event) = self.mapper.write_register(at, byte) {
+            self.events.push_back(event);
+        }
+    }
+
+    pub fn write_ram(&mut self, at: u16, byte: u8) {
+        self.ram_dirty |= self.mapper.ram_offset(at).is_some();
+        if let Some(event) = self.mapper.write_ram(&mut self.ram, at, byte) {
+            self.events.push_back(event);
+        }
+    }
+
+    pub fn receive_infrared(&mut self, light: bool) {
+        self.mapper.receive_infrared(light);
+    }
+
+    /// Rumble and infrared changes in the order they happened, the frontend drains these every frame
+    pub fn poll_event(&mut self) -> Option<CartridgeEvent> {
+        self.events.pop_front()
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,74 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use super::Cartridge;
+
+const FLUSH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Battery backed RAM persisted next to the ROM as `<rom>.sav`
+pub struct SaveFile {
+    path: PathBuf,
+    last_flush: Instant,
+    interval: Duration,
+}
+
+impl SaveFile {
+    pub fn new<P: Into<PathBuf>>(path: P) -> SaveFile {
+        SaveFile {
+            path: path.into(),
+            last_flush: Instant::now(),
+            interval: FLUSH_INTERVAL,
+        }
+    }
+
+    pub fn for_rom<P: AsRef<Path>>(rom_path: P) -> SaveFile {
+        SaveFile::new(rom_path.as_ref().with_extension("sav"))
+    }
+
+    pub fn with_interval(mut self, interval: Duration) -> SaveFile {
+        self.interval = interval;
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Fills cartridge RAM from disk. A missing save is not an error, it just means the game wasn't played yet, so Ok(false) is returned.
+    pub fn load(&self, cartridge: &mut Cartridge) -> io::Result<bool> {
+        let data = match fs::read(&self.path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
+            Err(e) => return Err(e),
+        };
+        cartridge.import_ram(&data).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        Ok(true)
+    }
+
+    /// Writes RAM into a temporary file first and renames it over the old save, so a crash mid write leaves the previous save intact
+    pub fn flush(&mut self, cartridge: &mut Cartridge) -> io::Result<()> {
+        let mut tmp_path = self.path.clone().into_os_string();
+        tmp_path.push(".tmp");
+        let tmp_path = PathBuf::from(tmp_path);
+        {
+            let mut file = fs::File::create(&tmp_path)?;
+            file.write_all(cartridge.export_ram())?;
+            file.sync_all()?;
+        }
+        fs::rename(&tmp_path, &self.path)?;
+        cartridge.mark_ram_clean();
+        self.last_flush = Instant::now();
+        Ok(())
+    }
+
+    /// Meant to be called from the main loop, flushes only when RAM changed and the interval has passed since the last flush
+    pub fn flush_if_due(&mut self, cartridge: &mut Cartridge) -> io::Result<bool> {
+        if !cartridge.is_ram_dirty() || self.last_flush.elapsed() < self.interval {
+            return Ok(false);
+        }
+        self.flush(cartridge)?;
+        Ok(true)
+    }
+}
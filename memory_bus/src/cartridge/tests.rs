@@ -0,0 +1,293 @@
+use super::*;
+use crate::MMU;
+
+/// ROM where the first byte of every bank holds the bank number, so a read tells us which bank got mapped in
+fn banked_rom(banks: usize, cartridge_type: u8) -> Vec<u8> {
+    let mut rom = vec![0; banks * ROM_BANK_SIZE];
+    for bank in 0..banks {
+        rom[bank * ROM_BANK_SIZE] = bank as u8;
+        rom[bank * ROM_BANK_SIZE + 1] = (bank >> 8) as u8;
+    }
+    rom[HEADER_CARTRIDGE_TYPE] = cartridge_type;
+    rom[HEADER_RAM_SIZE] = 0x03;
+    rom
+}
+
+fn prerequisites(banks: usize, cartridge_type: u8) -> MMU {
+    let mut mmu = MMU::new();
+    mmu.insert_cartridge(Cartridge::new(banked_rom(banks, cartridge_type)).unwrap());
+    mmu
+}
+
+fn mapped_bank(mmu: &MMU, at: u16) -> usize {
+    mmu.read_word(at) as usize
+}
+
+#[test]
+fn header_test() {
+    assert_eq!(Cartridge::new(vec![0; 0x100]).err(), Some(CartridgeError::TooSmall(0x100)));
+    assert_eq!(Cartridge::new(banked_rom(2, 0x22)).err(), Some(CartridgeError::UnsupportedType(0x22)));
+
+    let mut rom = banked_rom(2, 0x00);
+    rom[HEADER_TITLE..HEADER_TITLE + 6].copy_from_slice(b"TETRIS");
+    assert_eq!(Cartridge::new(rom).unwrap().title(), "TETRIS");
+
+    // all 11 bytes used, then a manufacturer code and the CGB flag
+    let mut rom = banked_rom(2, 0x00);
+    rom[HEADER_TITLE..HEADER_TITLE + 16].copy_from_slice(b"POKEMON_SLVAAXE\x80");
+    assert_eq!(Cartridge::new(rom).unwrap().title(), "POKEMON_SLV");
+    // CGB compatible but no manufacturer code, the title runs up to the flag
+    let mut rom = banked_rom(2, 0x00);
+    rom[HEADER_TITLE..HEADER_TITLE + 16].copy_from_slice(b"POKEMON YELLOW\0\x80");
+    assert_eq!(Cartridge::new(rom).unwrap().title(), "POKEMON YELLOW");
+    let mut rom = banked_rom(2, 0x00);
+    rom[HEADER_TITLE..HEADER_TITLE + 16].copy_from_slice(b"SUPER\x01GAME \xFF    ");
+    assert_eq!(Cartridge::new(rom).unwrap().title(), "SUPERGAME");
+}
+
+#[test]
+fn mbc1_test() {
+    let mut mbc = MBC1::default();
+    assert_eq!(mbc.rom_offset(0x4000), ROM_BANK_SIZE);
+    mbc.write_register(0x2000, 0x00); // bank 0 reads as 1
+    assert_eq!(mbc.rom_offset(0x4000), ROM_BANK_SIZE);
+    mbc.write_register(0x2000, 0xE5);
+    assert_eq!(mbc.rom_offset(0x4001), 0x05 * ROM_BANK_SIZE + 1);
+    mbc.write_register(0x4000, 0x02);
+    assert_eq!(mbc.rom_offset(0x4000), 0x45 * ROM_BANK_SIZE);
+    mbc.write_register(0x2000, 0x20); // 0x40 turns into 0x41
+    assert_eq!(mbc.rom_offset(0x4000), 0x41 * ROM_BANK_SIZE);
+    assert_eq!(mbc.rom_offset(0x0000), 0);
+    mbc.write_register(0x0000, 0x0A);
+    assert_eq!(mbc.ram_offset(0xA001), Some(1)); // mode 0, RAM stays on bank 0
+
+    mbc.write_register(0x6000, 0x01);
+    assert_eq!(mbc.rom_offset(0x0000), 0x40 * ROM_BANK_SIZE);
+    assert_eq!(mbc.ram_offset(0xA001), Some(2 * RAM_BANK_SIZE + 1));
+    mbc.write_register(0x0000, 0x00);
+    assert_eq!(mbc.ram_offset(0xA000), None);
+
+    let mut mmu = prerequisites(128, 0x03);
+    mmu.write_byte(0x2000, 0x1F);
+    mmu.write_byte(0x4000, 0x03);
+    assert_eq!(mapped_bank(&mmu, 0x4000), 0x7F);
+    assert_eq!(mapped_bank(&mmu, 0x0000), 0);
+    mmu.write_byte(0x0000, 0x0A);
+    mmu.write_byte(0xA000, 0x42);
+    assert_eq!(mmu.read_byte(0xA000), 0x42);
+    assert!(mmu.cartridge.as_ref().unwrap().has_battery());
+}
+
+#[test]
+fn mbc2_test() {
+    let mut mbc = MBC2::default();
+    assert_eq!(mbc.rom_offset(0x4000), 0x4000);
+    mbc.write_register(0x2100, 0x05);
+    assert_eq!(mbc.rom_offset(0x4123), 5 * ROM_BANK_SIZE + 0x123);
+    mbc.write_register(0x2100, 0x00);
+    assert_eq!(mbc.rom_offset(0x4000), ROM_BANK_SIZE);
+    mbc.write_register(0x2000, 0x07); // bit 8 clear, goes to RAM enable instead
+    assert_eq!(mbc.rom_offset(0x4000), ROM_BANK_SIZE);
+    assert_eq!(mbc.ram_offset(0xA000), None);
+    mbc.write_register(0x0000, 0x0A);
+    assert_eq!(mbc.ram_offset(0xA3FF), Some(0x1FF));
+
+    let mut mmu = prerequisites(16, 0x06);
+    mmu.write_byte(0x0100, 0x0F);
+    assert_eq!(mapped_bank(&mmu, 0x4000), 15);
+    assert_eq!(mmu.read_byte(0xA000), 0xFF);
+    mmu.write_byte(0x0000, 0x0A);
+    mmu.write_byte(0xA010, 0x5C);
+    assert_eq!(mmu.read_byte(0xA010), 0xFC);
+    assert_eq!(mmu.read_byte(0xA210), 0xFC);
+}
+
+#[test]
+fn mbc3_test() {
+    let mut mbc = MBC3::new(false);
+    mbc.write_register(0x2000, 0x00);
+    assert_eq!(mbc.rom_offset(0x4000), ROM_BANK_SIZE);
+    mbc.write_register(0x2000, 0xFF);
+    assert_eq!(mbc.rom_offset(0x4000), 0x7F * ROM_BANK_SIZE);
+    mbc.write_register(0x0000, 0x0A);
+    mbc.write_register(0x4000, 0x03);
+    assert_eq!(mbc.ram_offset(0xA001), Some(3 * RAM_BANK_SIZE + 1));
+    mbc.write_register(0x4000, 0x08); // no clock on this one
+    assert_eq!(mbc.read_ram(&[0; 4 * RAM_BANK_SIZE], 0xA000), 0xFF);
+
+    let mut mmu = prerequisites(128, 0x13);
+    mmu.write_byte(0x2000, 0x55);
+    assert_eq!(mapped_bank(&mmu, 0x4000), 0x55);
+    mmu.write_byte(0x0000, 0x0A);
+    mmu.write_byte(0x4000, 0x02);
+    mmu.write_byte(0xA000, 0x42);
+    mmu.write_byte(0x4000, 0x00);
+    assert_eq!(mmu.read_byte(0xA000), 0x00);
+    mmu.write_byte(0x4000, 0x02);
+    assert_eq!(mmu.read_byte(0xA000), 0x42);
+}
+
+#[test]
+fn mbc3_rtc_test() {
+    let mut mbc = MBC3::new(true);
+    let ram = &mut [0; RAM_BANK_SIZE][..];
+    mbc.write_register(0x0000, 0x0A);
+    // halt, set 1d 23:59:58, run again
+    mbc.write_register(0x4000, 0x0C);
+    mbc.write_ram(ram, 0xA000, 0x40);
+    for (register, value) in [(0x08, 58), (0x09, 59), (0x0A, 23), (0x0B, 1)].iter() {
+        mbc.write_register(0x4000, *register);
+        mbc.write_ram(ram, 0xA000, *value);
+    }
+    mbc.write_register(0x4000, 0x0C);
+    mbc.write_ram(ram, 0xA000, 0x00);
+    mbc.elapse(3);
+
+    // the registers read the latched copy until the next 0 -> 1 write
+    mbc.write_register(0x4000, 0x08);
+    assert_eq!(mbc.read_ram(ram, 0xA000), 0);
+    mbc.write_register(0x6000, 0x00);
+    mbc.write_register(0x6000, 0x01);
+    let mut registers = Vec::new();
+    for register in 0x08..=0x0C {
+        mbc.write_register(0x4000, register);
+        registers.push(mbc.read_ram(ram, 0xA000));
+    }
+    assert_eq!(registers, vec![1, 0, 0, 2, 0]);
+    mbc.elapse(60);
+    mbc.write_register(0x4000, 0x08);
+    assert_eq!(mbc.read_ram(ram, 0xA000), 1);
+    mbc.write_register(0x6000, 0x01); // not armed without the 0 first
+    assert_eq!(mbc.read_ram(ram, 0xA000), 1);
+
+    // day 511 rolls over into the carry flag
+    mbc.write_register(0x4000, 0x0B);
+    mbc.write_ram(ram, 0xA000, 0xFF);
+    mbc.write_register(0x4000, 0x0C);
+    mbc.write_ram(ram, 0xA000, 0x01);
+    mbc.elapse(24 * 60 * 60);
+    mbc.write_register(0x6000, 0x00);
+    mbc.write_register(0x6000, 0x01);
+    assert_eq!(mbc.read_ram(ram, 0xA000), 0x80);
+    mbc.write_register(0x4000, 0x0B);
+    assert_eq!(mbc.read_ram(ram, 0xA000), 0x00);
+    // RAM banks still work next to the clock
+    mbc.write_register(0x4000, 0x01);
+    mbc.write_ram(ram, 0xA000, 0x42);
+    assert_eq!(mbc.read_ram(ram, 0xA000), 0x42);
+}
+
+#[test]
+fn mbc5_test() {
+    let mut mbc = MBC5::new(false);
+    mbc.write_register(0x2000, 0x00);
+    assert_eq!(mbc.rom_offset(0x4000), 0);
+    mbc.write_register(0x2000, 0x34);
+    mbc.write_register(0x3000, 0x01);
+    assert_eq!(mbc.rom_offset(0x4001), 0x134 * ROM_BANK_SIZE + 1);
+    mbc.write_register(0x0000, 0x0A);
+    mbc.write_register(0x4000, 0x0F);
+    assert_eq!(mbc.ram_offset(0xA001), Some(15 * RAM_BANK_SIZE + 1));
+
+    let mut mmu = prerequisites(512, 0x1B);
+    mmu.write_byte(0x2000, 0xFF);
+    mmu.write_byte(0x3000, 0x01);
+    assert_eq!(mapped_bank(&mmu, 0x4000), 0x1FF);
+    assert_eq!(mapped_bank(&mmu, 0x0000), 0);
+}
+
+#[test]
+fn mbc5_rumble_test() {
+    let mut mmu = prerequisites(4, 0x1C);
+    mmu.write_byte(0x4000, 0x0B);
+    mmu.write_byte(0x4000, 0x0B);
+    mmu.write_byte(0x4000, 0x03);
+    let cartridge = mmu.cartridge.as_mut().unwrap();
+    assert_eq!(cartridge.poll_event(), Some(CartridgeEvent::Rumble(true)));
+    assert_eq!(cartridge.poll_event(), Some(CartridgeEvent::Rumble(false)));
+    assert_eq!(cartridge.poll_event(), None);
+
+    let mut mbc = MBC5::new(true);
+    mbc.write_register(0x0000, 0x0A);
+    mbc.write_register(0x4000, 0x0B);
+    assert_eq!(mbc.ram_offset(0xA000), Some(3 * RAM_BANK_SIZE));
+}
+
+#[test]
+fn huc1_test() {
+    let mut mbc = HuC1::default();
+    mbc.write_register(0x2000, 0x3F);
+    assert_eq!(mbc.rom_offset(0x7FFF), 0x3F * ROM_BANK_SIZE + 0x3FFF);
+    mbc.write_register(0x4000, 0x02);
+    assert_eq!(mbc.ram_offset(0xA000), Some(2 * RAM_BANK_SIZE));
+    mbc.write_register(0x0000, 0x0E);
+    assert_eq!(mbc.ram_offset(0xA000), None);
+
+    let mut mmu = prerequisites(64, 0xFF);
+    mmu.write_byte(0xA000, 0x42);
+    assert_eq!(mmu.read_byte(0xA000), 0x42);
+    mmu.write_byte(0x0000, 0x0E);
+    assert_eq!(mmu.read_byte(0xA000), 0xC0);
+    mmu.write_byte(0xA000, 0x01);
+    let cartridge = mmu.cartridge.as_mut().unwrap();
+    cartridge.receive_infrared(true);
+    assert_eq!(cartridge.poll_event(), Some(CartridgeEvent::Infrared(true)));
+    assert_eq!(mmu.read_byte(0xA000), 0xC1);
+    mmu.write_byte(0x0000, 0x0A);
+    assert_eq!(mmu.read_byte(0xA000), 0x42);
+}
+
+#[test]
+fn mmm01_test() {
+    let mut mbc = MMM01::new(64);
+    assert_eq!(mbc.rom_offset(0x0000), 62 * ROM_BANK_SIZE);
+    assert_eq!(mbc.rom_offset(0x4000), 63 * ROM_BANK_SIZE);
+
+    // menu picks the game at banks 0x24-0x27, bits 2-4 of the bank number get frozen
+    mbc.write_register(0x6000, 0b0011_1000);
+    mbc.write_register(0x2000, 0b0010_0100);
+    mbc.write_register(0x0000, 0x40);
+    assert_eq!(mbc.rom_offset(0x0000), 0x24 * ROM_BANK_SIZE);
+    assert_eq!(mbc.rom_offset(0x4000), 0x25 * ROM_BANK_SIZE);
+
+    mbc.write_register(0x2000, 0x03);
+    assert_eq!(mbc.rom_offset(0x4000), 0x27 * ROM_BANK_SIZE);
+    mbc.write_register(0x2000, 0x1F); // masked bits stay frozen
+    assert_eq!(mbc.rom_offset(0x4000), 0x27 * ROM_BANK_SIZE);
+    mbc.write_register(0x4000, 0x30); // outer bits are locked now
+    assert_eq!(mbc.rom_offset(0x4000), 0x27 * ROM_BANK_SIZE);
+
+    let mut mmu = prerequisites(8, 0x0B);
+    assert_eq!(mapped_bank(&mmu, 0x0000), 6);
+    assert_eq!(mapped_bank(&mmu, 0x4000), 7);
+    mmu.write_byte(0x2000, 0x02);
+    mmu.write_byte(0x0000, 0x40);
+    assert_eq!(mapped_bank(&mmu, 0x0000), 0);
+    assert_eq!(mapped_bank(&mmu, 0x4000), 2);
+}
+
+#[test]
+fn save_ram_test() {
+    let mut cartridge = Cartridge::new(banked_rom(4, 0x1B)).unwrap();
+    assert!(cartridge.has_battery());
+    assert!(!Cartridge::new(banked_rom(4, 0x19)).unwrap().has_battery());
+
+    assert_eq!(cartridge.import_ram(&[0; 16]), Err(CartridgeError::SaveSizeMismatch { expected: 4 * RAM_BANK_SIZE, actual: 16 }));
+    cartridge.write_rom(0x0000, 0x0A);
+    cartridge.write_ram(0xA005, 0x77);
+    assert!(cartridge.is_ram_dirty());
+    assert_eq!(cartridge.export_ram()[5], 0x77);
+
+    let path = std::env::temp_dir().join(format!("memory_bus_save_ram_test_{}.sav", std::process::id()));
+    let mut save = SaveFile::new(&path);
+    save.flush(&mut cartridge).unwrap();
+    assert!(!cartridge.is_ram_dirty());
+    assert!(!save.flush_if_due(&mut cartridge).unwrap());
+
+    let mut restored = Cartridge::new(banked_rom(4, 0x1B)).unwrap();
+    assert!(save.load(&mut restored).unwrap());
+    restored.write_rom(0x0000, 0x0A);
+    assert_eq!(restored.read_ram(0xA005), 0x77);
+    std::fs::remove_file(&path).unwrap();
+    assert!(!save.load(&mut restored).unwrap());
+}
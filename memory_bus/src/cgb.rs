@@ -0,0 +1,148 @@
+use crate::io::IoDevice;
+
+pub const KEY1: u16 = 0xFF4D;
+pub const VBK: u16 = 0xFF4F;
+pub const HDMA1: u16 = 0xFF51;
+pub const HDMA2: u16 = 0xFF52;
+pub const HDMA3: u16 = 0xFF53;
+pub const HDMA4: u16 = 0xFF54;
+pub const HDMA5: u16 = 0xFF55;
+pub const SVBK: u16 = 0xFF70;
+
+pub const WRAM_BANK_SIZE: usize = 0x1000;
+pub const WRAM_BANKS: usize = 8;
+
+/// CPU clocks the HDMA keeps the CPU halted for every 16 bytes, doubled in double speed mode since the copy runs at the normal speed
+const HDMA_BLOCK_CLOCKS: usize = 32;
+
+/// General purpose and HBlank VRAM DMA set up through HDMA1-HDMA5
+#[derive(Debug, Default)]
+pub struct Hdma {
+    source: u16,
+    destination: u16,
+    remaining: u8,
+    hblank_active: bool,
+}
+
+impl Hdma {
+    pub fn is_hblank_active(&self) -> bool {
+        self.hblank_active
+    }
+
+    pub(crate) fn next_block_pending(&self) -> bool {
+        self.remaining > 0
+    }
+
+    /// Source and destination of the next 16 byte block, destination is always somewhere in VRAM
+    pub(crate) fn next_block(&mut self) -> Option<(u16, u16)> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let block = (self.source, 0x8000 | (self.destination & 0x1FF0));
+        self.source = self.source.wrapping_add(0x10);
+        self.destination = self.destination.wrapping_add(0x10);
+        self.remaining -= 1;
+        if self.remaining == 0 {
+            self.hblank_active = false;
+        }
+        Some(block)
+    }
+}
+
+/// Registers only present on Game Boy Color. In DMG mode they read as 0xFF and ignore writes.
+#[derive(Debug)]
+pub struct CgbRegisters {
+    pub enabled: bool,
+    pub hdma: Hdma,
+    vram_bank: u8,
+    wram_bank: u8,
+    speed_switch_armed: bool,
+    double_speed: bool,
+}
+
+impl Default for CgbRegisters {
+    fn default() -> CgbRegisters {
+        CgbRegisters {
+            enabled: false,
+            hdma: Hdma::default(),
+            vram_bank: 0,
+            wram_bank: 1,
+            speed_switch_armed: false,
+            double_speed: false,
+        }
+    }
+}
+
+impl CgbRegisters {
+    pub fn vram_bank(&self) -> usize {
+        if self.enabled { self.vram_bank as usize } else { 0 }
+    }
+
+    /// Bank mapped at 0xD000-0xDFFF, bank 0 can't be selected there and gives bank 1 instead
+    pub fn wram_bank(&self) -> usize {
+        if self.enabled { self.wram_bank.max(1) as usize } else { 1 }
+    }
+
+    pub fn is_double_speed(&self) -> bool {
+        self.double_speed
+    }
+
+    /// STOP with KEY1 bit 0 set toggles CPU speed, returns whether a switch happened
+    pub fn perform_speed_switch(&mut self) -> bool {
+        if !self.enabled || !self.speed_switch_armed {
+            return false;
+        }
+        self.speed_switch_armed = false;
+        self.double_speed = !self.double_speed;
+        true
+    }
+
+    pub(crate) fn hdma_block_clocks(&self) -> usize {
+        if self.double_speed { HDMA_BLOCK_CLOCKS * 2 } else { HDMA_BLOCK_CLOCKS }
+    }
+
+    /// Handles a write to HDMA5, returns true when a general purpose transfer should run right away
+    pub(crate) fn start_hdma(&mut self, byte: u8) -> bool {
+        if self.hdma.hblank_active && byte & 0x80 == 0 {
+            self.hdma.hblank_active = false; // cancels the HBlank transfer, what's left stays in HDMA5
+            return false;
+        }
+        self.hdma.remaining = (byte & 0x7F) + 1;
+        self.hdma.hblank_active = byte & 0x80 > 0;
+        !self.hdma.hblank_active
+    }
+}
+
+impl IoDevice for CgbRegisters {
+    fn read_io(&self, at: u16) -> u8 {
+        if !self.enabled {
+            return 0xFF;
+        }
+        match at {
+            KEY1 => 0x7E | ((self.double_speed as u8) << 7) | self.speed_switch_armed as u8,
+            VBK => 0xFE | self.vram_bank,
+            SVBK => 0xF8 | self.wram_bank,
+            HDMA5 => {
+                let remaining = self.hdma.remaining.wrapping_sub(1) & 0x7F;
+                if self.hdma.hblank_active { remaining } else { 0x80 | remaining }
+            }
+            _ => 0xFF, // HDMA1-HDMA4 are write only
+        }
+    }
+
+    fn write_io(&mut self, at: u16, byte: u8) {
+        if !self.enabled {
+            return;
+        }
+        match at {
+            KEY1 => self.speed_switch_armed = byte & 0x01 > 0,
+            VBK => self.vram_bank = byte & 0x01,
+            SVBK => self.wram_bank = byte & 0x07,
+            HDMA1 => self.hdma.source = (self.hdma.source & 0x00FF) | ((byte as u16) << 8),
+            HDMA2 => self.hdma.source = (self.hdma.source & 0xFF00) | (byte & 0xF0) as u16,
+            HDMA3 => self.hdma.destination = (self.hdma.destination & 0x00FF) | (((byte & 0x1F) as u16) << 8),
+            HDMA4 => self.hdma.destination = (self.hdma.destination & 0xFF00) | (byte & 0xF0) as u16,
+            _ => {}
+        }
+    }
+}
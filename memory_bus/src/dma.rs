@@ -0,0 +1,69 @@
+pub const OAM_START: u16 = 0xFE00;
+pub const OAM_SIZE: u16 = 0xA0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DmaState {
+    Idle,
+    Starting,
+    Active,
+}
+
+/// OAM DMA started by a write to 0xFF46. After one M-cycle of setup it copies a byte per M-cycle from XX00-XX9F into OAM,
+/// and while it runs the CPU can only reach 0xFF00-0xFFFF, which is why games run their DMA routine from HRAM.
+#[derive(Debug)]
+pub struct OamDma {
+    state: DmaState,
+    source: u16,
+    index: u16,
+    restarted: bool,
+}
+
+impl Default for OamDma {
+    fn default() -> OamDma {
+        OamDma {
+            state: DmaState::Idle,
+            source: 0,
+            index: 0,
+            restarted: false,
+        }
+    }
+}
+
+impl OamDma {
+    pub fn start(&mut self, page: u8) {
+        let source = (page as u16) << 8;
+        // sources past WRAM read the echo instead of OAM and IO
+        self.source = if source >= 0xE000 { source - 0x2000 } else { source };
+        self.index = 0;
+        self.restarted = self.state != DmaState::Idle;
+        self.state = DmaState::Starting;
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.state == DmaState::Active
+    }
+
+    /// Bus blocking only kicks in once bytes start moving, restarting a running transfer keeps the bus blocked through the setup cycle
+    pub fn blocks_bus(&self) -> bool {
+        self.state == DmaState::Active || (self.state == DmaState::Starting && self.restarted)
+    }
+
+    /// Advances one M-cycle, returns the (source, destination) pair to copy if a byte moves this cycle
+    pub fn step(&mut self) -> Option<(u16, u16)> {
+        match self.state {
+            DmaState::Idle => None,
+            DmaState::Starting => {
+                self.state = DmaState::Active;
+                None
+            }
+            DmaState::Active => {
+                let transfer = (self.source + self.index, OAM_START + self.index);
+                self.index += 1;
+                if self.index == OAM_SIZE {
+                    self.state = DmaState::Idle;
+                }
+                Some(transfer)
+            }
+        }
+    }
+}
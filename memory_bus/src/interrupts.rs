@@ -0,0 +1,74 @@
+use crate::io::{IoDevice, IE, IF};
+
+/// Interrupt sources in priority order, the discriminant is the bit in IE/IF
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interrupt {
+    VBlank = 0,
+    Stat = 1,
+    Timer = 2,
+    Serial = 3,
+    Joypad = 4,
+}
+
+impl Interrupt {
+    const ALL: [Interrupt; 5] = [Interrupt::VBlank, Interrupt::Stat, Interrupt::Timer, Interrupt::Serial, Interrupt::Joypad];
+
+    pub const fn mask(self) -> u8 {
+        1 << self as u8
+    }
+
+    /// Address the CPU jumps to when servicing this interrupt
+    pub const fn vector(self) -> u16 {
+        0x40 + 8 * self as u16
+    }
+}
+
+/// IF and IE. Only the low 5 bits of IF exist and the rest read back as 1, IE is a plain 8 bit register.
+#[derive(Debug, Default)]
+pub struct InterruptController {
+    pub enable: u8,
+    flags: u8,
+}
+
+impl InterruptController {
+    pub fn request(&mut self, interrupt: Interrupt) {
+        self.flags |= interrupt.mask();
+    }
+
+    pub fn clear(&mut self, interrupt: Interrupt) {
+        self.flags &= !interrupt.mask();
+    }
+
+    pub fn is_requested(&self, interrupt: Interrupt) -> bool {
+        self.flags & interrupt.mask() > 0
+    }
+
+    /// Highest priority interrupt that is both requested and enabled, regardless of IME
+    pub fn pending(&self) -> Option<Interrupt> {
+        let pending = self.enable & self.flags & 0x1F;
+        Interrupt::ALL.iter().copied().find(|interrupt| pending & interrupt.mask() > 0)
+    }
+
+    /// Whether HALT should wake up, which also doesn't care about IME
+    pub fn has_pending(&self) -> bool {
+        self.pending().is_some()
+    }
+}
+
+impl IoDevice for InterruptController {
+    fn read_io(&self, at: u16) -> u8 {
+        match at {
+            IF => 0xE0 | self.flags,
+            IE => self.enable,
+            _ => 0xFF,
+        }
+    }
+
+    fn write_io(&mut self, at: u16, byte: u8) {
+        match at {
+            IF => self.flags = byte & 0x1F,
+            IE => self.enable = byte,
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,147 @@
+/// First and last address of the IO register block
+pub const IO_START: u16 = 0xFF00;
+pub const IO_END: u16 = 0xFF7F;
+
+pub const P1: u16 = 0xFF00;
+pub const SB: u16 = 0xFF01;
+pub const SC: u16 = 0xFF02;
+pub const DIV: u16 = 0xFF04;
+pub const TIMA: u16 = 0xFF05;
+pub const TMA: u16 = 0xFF06;
+pub const TAC: u16 = 0xFF07;
+pub const IF: u16 = 0xFF0F;
+pub const LCDC: u16 = 0xFF40;
+pub const STAT: u16 = 0xFF41;
+pub const SCY: u16 = 0xFF42;
+pub const SCX: u16 = 0xFF43;
+pub const LY: u16 = 0xFF44;
+pub const LYC: u16 = 0xFF45;
+pub const DMA: u16 = 0xFF46;
+pub const BGP: u16 = 0xFF47;
+pub const OBP0: u16 = 0xFF48;
+pub const OBP1: u16 = 0xFF49;
+pub const WY: u16 = 0xFF4A;
+pub const WX: u16 = 0xFF4B;
+/// Interrupt enable, outside the IO block but owned by the interrupt controller
+pub const IE: u16 = 0xFFFF;
+
+/// Anything that owns a slice of the IO register block. Reads and writes here are the CPU's view, masks are applied by the implementor.
+pub trait IoDevice {
+    fn read_io(&self, at: u16) -> u8;
+    fn write_io(&mut self, at: u16, byte: u8);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IoOwner {
+    Joypad,
+    Serial,
+    Timer,
+    Interrupts,
+    Apu,
+    Ppu,
+    BootRom,
+    Cgb,
+    Unmapped,
+}
+
+pub const fn owner(at: u16) -> IoOwner {
+    match at {
+        0xFF00 => IoOwner::Joypad,
+        0xFF01..=0xFF02 => IoOwner::Serial,
+        0xFF04..=0xFF07 => IoOwner::Timer,
+        0xFF0F => IoOwner::Interrupts,
+        0xFF10..=0xFF14 | 0xFF16..=0xFF1E | 0xFF20..=0xFF26 | 0xFF30..=0xFF3F => IoOwner::Apu,
+        0xFF40..=0xFF4B => IoOwner::Ppu,
+        0xFF50 => IoOwner::BootRom,
+        0xFF4D | 0xFF4F | 0xFF51..=0xFF55 | 0xFF70 => IoOwner::Cgb,
+        _ => IoOwner::Unmapped,
+    }
+}
+
+/// Which bits of a register the CPU can actually see and touch
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RegisterSpec {
+    /// not wired at all, always read back as 1
+    pub unused: u8,
+    /// driven by hardware, CPU writes don't stick
+    pub read_only: u8,
+    /// stored, but read back as 1
+    pub write_only: u8,
+}
+
+impl RegisterSpec {
+    const fn new(unused: u8, read_only: u8, write_only: u8) -> RegisterSpec {
+        RegisterSpec { unused, read_only, write_only }
+    }
+
+    pub const fn read(&self, value: u8) -> u8 {
+        value | self.unused | self.write_only
+    }
+
+    pub const fn write(&self, old: u8, byte: u8) -> u8 {
+        let locked = self.unused | self.read_only;
+        (old & locked) | (byte & !locked)
+    }
+}
+
+const PLAIN: RegisterSpec = RegisterSpec::new(0x00, 0x00, 0x00);
+const UNMAPPED: RegisterSpec = RegisterSpec::new(0xFF, 0x00, 0x00);
+
+/// DMG register layout
+pub const fn spec(at: u16) -> RegisterSpec {
+    match at {
+        P1 => RegisterSpec::new(0xC0, 0x0F, 0x00),
+        SC => RegisterSpec::new(0x7E, 0x00, 0x00),
+        DIV => RegisterSpec::new(0x00, 0xFF, 0x00),
+        TAC => RegisterSpec::new(0xF8, 0x00, 0x00),
+        IF => RegisterSpec::new(0xE0, 0x00, 0x00),
+        0xFF10 => RegisterSpec::new(0x80, 0x00, 0x00), // NR10
+        0xFF11 | 0xFF16 => RegisterSpec::new(0x00, 0x00, 0x3F), // NR11, NR21 length
+        0xFF13 | 0xFF18 | 0xFF1B | 0xFF1D | 0xFF20 => RegisterSpec::new(0x00, 0x00, 0xFF), // frequency low and lengths
+        0xFF14 | 0xFF19 | 0xFF1E | 0xFF23 => RegisterSpec::new(0x38, 0x00, 0x87), // NRx4, only length enable is readable
+        0xFF1A => RegisterSpec::new(0x7F, 0x00, 0x00), // NR30
+        0xFF1C => RegisterSpec::new(0x9F, 0x00, 0x00), // NR32
+        0xFF26 => RegisterSpec::new(0x70, 0x0F, 0x00), // NR52, channel status is read only
+        STAT => RegisterSpec::new(0x80, 0x07, 0x00),
+        LY => RegisterSpec::new(0x00, 0xFF, 0x00),
+        0xFF12 | 0xFF17 | 0xFF21 | 0xFF22 | 0xFF24 | 0xFF25 | 0xFF30..=0xFF3F => PLAIN,
+        SB | TIMA | TMA | LCDC | SCY | SCX | LYC | DMA | BGP | OBP0 | OBP1 | WY | WX => PLAIN,
+        _ => UNMAPPED,
+    }
+}
+
+/// Backing store for registers whose owner doesn't have a component of its own
+pub struct IoRegisters {
+    registers: [u8; (IO_END - IO_START) as usize + 1],
+}
+
+impl Default for IoRegisters {
+    fn default() -> IoRegisters {
+        IoRegisters {
+            registers: [0; (IO_END - IO_START) as usize + 1],
+        }
+    }
+}
+
+impl IoRegisters {
+    /// Raw value, as seen by the hardware that owns the register
+    pub fn get(&self, at: u16) -> u8 {
+        self.registers[(at - IO_START) as usize]
+    }
+
+    /// Raw store, bypassing read only masks, meant for the owning hardware like the PPU updating LY
+    pub fn set(&mut self, at: u16, byte: u8) {
+        self.registers[(at - IO_START) as usize] = byte;
+    }
+}
+
+impl IoDevice for IoRegisters {
+    fn read_io(&self, at: u16) -> u8 {
+        spec(at).read(self.get(at))
+    }
+
+    fn write_io(&mut self, at: u16, byte: u8) {
+        let value = spec(at).write(self.get(at), byte);
+        self.set(at, value);
+    }
+}
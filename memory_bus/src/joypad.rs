@@ -0,0 +1,105 @@
+use std::ops::BitOr;
+
+use crate::io::{IoDevice, P1};
+
+/// Set of pressed buttons, combine with `|`. The low nibble is the direction pad and the high nibble the action buttons,
+/// each in the order P1 reports them.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Buttons(u8);
+
+impl Buttons {
+    pub const NONE: Buttons = Buttons(0);
+    pub const RIGHT: Buttons = Buttons(0x01);
+    pub const LEFT: Buttons = Buttons(0x02);
+    pub const UP: Buttons = Buttons(0x04);
+    pub const DOWN: Buttons = Buttons(0x08);
+    pub const A: Buttons = Buttons(0x10);
+    pub const B: Buttons = Buttons(0x20);
+    pub const SELECT: Buttons = Buttons(0x40);
+    pub const START: Buttons = Buttons(0x80);
+
+    pub fn contains(&self, other: Buttons) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    fn directions(&self) -> u8 {
+        self.0 & 0x0F
+    }
+
+    fn actions(&self) -> u8 {
+        self.0 >> 4
+    }
+}
+
+impl BitOr for Buttons {
+    type Output = Buttons;
+
+    fn bitor(self, rhs: Buttons) -> Buttons {
+        Buttons(self.0 | rhs.0)
+    }
+}
+
+/// P1. Bits 4 and 5 select the direction and action lines (active low), and the low nibble reads 0 for every pressed
+/// button on a selected line. Any of those bits going from 1 to 0 requests the joypad interrupt.
+#[derive(Debug)]
+pub struct Joypad {
+    select: u8,
+    pressed: Buttons,
+    interrupt: bool,
+}
+
+impl Default for Joypad {
+    fn default() -> Joypad {
+        Joypad { select: 0x30, pressed: Buttons::NONE, interrupt: false }
+    }
+}
+
+impl Joypad {
+    /// Replaces the whole set of pressed buttons, whatever the input comes from
+    pub fn set_buttons(&mut self, buttons: Buttons) {
+        self.update(|joypad| joypad.pressed = buttons);
+    }
+
+    pub fn buttons(&self) -> Buttons {
+        self.pressed
+    }
+
+    /// Returns true once after a high to low transition on any input line
+    pub fn take_interrupt(&mut self) -> bool {
+        std::mem::replace(&mut self.interrupt, false)
+    }
+
+    fn lines(&self) -> u8 {
+        let mut low = 0;
+        if self.select & 0x10 == 0 {
+            low |= self.pressed.directions();
+        }
+        if self.select & 0x20 == 0 {
+            low |= self.pressed.actions();
+        }
+        !low & 0x0F
+    }
+
+    fn update<F: FnOnce(&mut Joypad)>(&mut self, change: F) {
+        let old = self.lines();
+        change(self);
+        if old & !self.lines() > 0 {
+            self.interrupt = true;
+        }
+    }
+}
+
+impl IoDevice for Joypad {
+    fn read_io(&self, at: u16) -> u8 {
+        match at {
+            P1 => 0xC0 | self.select | self.lines(),
+            _ => 0xFF,
+        }
+    }
+
+    fn write_io(&mut self, at: u16, byte: u8) {
+        if at == P1 {
+            self.update(|joypad| joypad.select = byte & 0x30);
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use crate::access::PPUMode;
+use crate::io::{IoDevice, LCDC, LY, LYC, SCX, SCY, STAT, WX, WY};
+
+/// LCDC, STAT, the scroll and window positions, LY and LYC. The PPU drives LY and the mode through `set_ly` and
+/// `set_mode`, the CPU only gets the writable bits. STAT interrupts fire on the rising edge of the OR of all enabled
+/// sources, so a source going high while another one already holds the line up is swallowed (STAT blocking).
+#[derive(Debug)]
+pub struct Lcd {
+    pub lcdc: u8,
+    pub scy: u8,
+    pub scx: u8,
+    pub wy: u8,
+    pub wx: u8,
+    lyc: u8,
+    ly: u8,
+    // STAT bits 3-6, the interrupt sources
+    stat_select: u8,
+    mode: PPUMode,
+    stat_line: bool,
+    interrupt: bool,
+}
+
+impl Default for Lcd {
+    fn default() -> Lcd {
+        Lcd {
+            lcdc: 0,
+            scy: 0,
+            scx: 0,
+            wy: 0,
+            wx: 0,
+            lyc: 0,
+            ly: 0,
+            stat_select: 0,
+            mode: PPUMode::HBlank, // LCD off reports mode 0, nothing is locked
+            stat_line: false,
+            interrupt: false,
+        }
+    }
+}
+
+impl Lcd {
+    pub fn is_enabled(&self) -> bool {
+        self.lcdc & 0x80 > 0
+    }
+
+    pub fn ly(&self) -> u8 {
+        self.ly
+    }
+
+    pub fn set_ly(&mut self, ly: u8) {
+        self.update(|lcd| lcd.ly = ly);
+    }
+
+    pub fn mode(&self) -> PPUMode {
+        self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: PPUMode) {
+        self.update(|lcd| lcd.mode = mode);
+    }
+
+    pub fn coincidence(&self) -> bool {
+        self.ly == self.lyc
+    }
+
+    /// Returns true once after the STAT interrupt line went high
+    pub fn take_interrupt(&mut self) -> bool {
+        std::mem::replace(&mut self.interrupt, false)
+    }
+
+    fn stat(&self) -> u8 {
+        0x80 | self.stat_select | (self.coincidence() as u8) << 2 | self.mode as u8
+    }
+
+    fn stat_line(&self) -> bool {
+        let source = |bit: u8, active: bool| self.stat_select & (1 << bit) > 0 && active;
+        source(3, self.mode == PPUMode::HBlank)
+            || source(4, self.mode == PPUMode::VBlank)
+            || source(5, self.mode == PPUMode::ScanlineOAM)
+            || source(6, self.coincidence())
+    }
+
+    fn update<F: FnOnce(&mut Lcd)>(&mut self, change: F) {
+        change(self);
+        let line = self.stat_line();
+        if line && !self.stat_line {
+            self.interrupt = true;
+        }
+        self.stat_line = line;
+    }
+}
+
+impl IoDevice for Lcd {
+    fn read_io(&self, at: u16) -> u8 {
+        match at {
+            LCDC => self.lcdc,
+            STAT => self.stat(),
+            SCY => self.scy,
+            SCX => self.scx,
+            LY => self.ly,
+            LYC => self.lyc,
+            WY => self.wy,
+            WX => self.wx,
+            _ => 0xFF,
+        }
+    }
+
+    fn write_io(&mut self, at: u16, byte: u8) {
+        match at {
+            LCDC => self.lcdc = byte,
+            STAT => self.update(|lcd| lcd.stat_select = byte & 0x78),
+            SCY => self.scy = byte,
+            SCX => self.scx = byte,
+            LYC => self.update(|lcd| lcd.lyc = byte),
+            WY => self.wy = byte,
+            WX => self.wx = byte,
+            _ => {}
+        }
+    }
+}
@@ -1,71 +1,377 @@
-pub struct CharacterRAM {
-    tiles: Vec<u8>,
-}
+pub mod access;
+pub mod cartridge;
+pub mod cgb;
+pub mod dma;
+pub mod interrupts;
+pub mod io;
+pub mod joypad;
+pub mod lcd;
+pub mod serial;
+pub mod timer;
+pub mod vram;
+pub mod watch;
 
-impl Default for CharacterRAM {
-    fn default() -> CharacterRAM {
-        CharacterRAM {
-            tiles: Vec::with_capacity(0x1800)
-        }
-    }
-}
+use std::cell::RefCell;
 
-pub struct BGMapData {
-    tile_index: Vec<u8>,
-}
+use access::Accuracy;
+use cartridge::Cartridge;
+use cgb::{CgbRegisters, WRAM_BANK_SIZE, WRAM_BANKS};
+use dma::OamDma;
+use interrupts::{Interrupt, InterruptController};
+use io::{IoDevice, IoOwner, IoRegisters, IO_END, IO_START};
+use joypad::{Buttons, Joypad};
+use lcd::Lcd;
+use serial::Serial;
+use timer::Timer;
+use watch::{Access, Watches, Watchpoint, WatchHit, WatchId};
+use vram::{BG_MAP_1_START, BG_MAP_2_START, TILE_DATA_END, VRAM_END, VRAM_START};
 
-impl Default for BGMapData {
-    fn default() -> BGMapData {
-        BGMapData {
-            tile_index: Vec::with_capacity(0xFF)
-        }
-    }
-}
+pub use access::PPUMode;
+pub use vram::{BGMapData, CharacterRAM};
+
+/// Writing anything but zero here unmaps the boot ROM until the next power cycle
+pub const BOOT_ROM_DISABLE: u16 = 0xFF50;
+
+const DMG_BOOT_ROM_SIZE: u16 = 0x100;
+const CGB_BOOT_ROM_SIZE: u16 = 0x900;
 
 pub struct MMU {
     pub ram: Vec<u8>,
-    // pub bgmapdata1: BGMapData,
-    // pub bgmapdata2: BGMapData,
-    // pub cram: CharacterRAM,
+    pub cartridge: Option<Cartridge>,
+    pub io: IoRegisters,
+    pub timer: Timer,
+    pub interrupts: InterruptController,
+    pub joypad: Joypad,
+    pub serial: Serial,
+    pub lcd: Lcd,
+    pub dma: OamDma,
+    boot_rom: Option<Vec<u8>>,
+    clocks: usize,
+    pub bgmapdata1: BGMapData,
+    pub bgmapdata2: BGMapData,
+    pub cram: CharacterRAM,
+    pub cgb: CgbRegisters,
+    // CGB VRAM bank 1, the maps hold tile attributes there
+    pub bgattrdata1: BGMapData,
+    pub bgattrdata2: BGMapData,
+    pub cram_bank1: CharacterRAM,
+    // CGB WRAM banks 2-7, banks 0 and 1 stay in ram
+    wram_banks: Vec<u8>,
+    stall_clocks: usize,
+    pub accuracy: Accuracy,
+    watches: RefCell<Watches>,
 }
 
 impl MMU {
     pub fn new() -> MMU {
         let ram = vec![0;0x10000];
         MMU {
-            ram
+            ram,
+            cartridge: None,
+            io: IoRegisters::default(),
+            timer: Timer::default(),
+            interrupts: InterruptController::default(),
+            joypad: Joypad::default(),
+            serial: Serial::default(),
+            lcd: Lcd::default(),
+            dma: OamDma::default(),
+            boot_rom: None,
+            clocks: 0,
+            bgmapdata1: BGMapData::default(),
+            bgmapdata2: BGMapData::default(),
+            cram: CharacterRAM::default(),
+            cgb: CgbRegisters::default(),
+            bgattrdata1: BGMapData::default(),
+            bgattrdata2: BGMapData::default(),
+            cram_bank1: CharacterRAM::default(),
+            wram_banks: vec![0; (WRAM_BANKS - 2) * WRAM_BANK_SIZE],
+            stall_clocks: 0,
+            accuracy: Accuracy::default(),
+            watches: RefCell::new(Watches::default()),
+        }
+    }
+
+    pub fn add_watchpoint(&mut self, watchpoint: Watchpoint) -> WatchId {
+        self.watches.get_mut().add(watchpoint)
+    }
+
+    pub fn remove_watchpoint(&mut self, id: WatchId) {
+        self.watches.get_mut().remove(id);
+    }
+
+    /// The CPU tells the bus which instruction it's running, so watch hits can report it
+    pub fn set_pc(&self, pc: u16) {
+        self.watches.borrow_mut().set_pc(pc);
+    }
+
+    /// Instruction at `pc` is about to run
+    pub fn watch_execute(&self, pc: u16) {
+        let mut watches = self.watches.borrow_mut();
+        if !watches.is_empty() {
+            let opcode = self.read_bus(pc);
+            watches.set_pc(pc);
+            watches.check(Access::EXECUTE, pc, opcode, opcode);
+        }
+    }
+
+    /// Watchpoint that asked to stop emulation, if any fired since the last call
+    pub fn take_watch_hit(&self) -> Option<WatchHit> {
+        self.watches.borrow_mut().take_hit()
+    }
+
+    /// The PPU reports every mode change here so CPU accesses to VRAM and OAM can be locked
+    pub fn set_ppu_mode(&mut self, mode: PPUMode) {
+        self.lcd.set_mode(mode);
+    }
+
+    pub fn ppu_mode(&self) -> PPUMode {
+        self.lcd.mode()
+    }
+
+    fn is_locked_by_ppu(&self, at: u16) -> bool {
+        self.accuracy == Accuracy::Strict && access::is_locked(self.lcd.mode(), at)
+    }
+
+    /// Turns on VRAM and WRAM banking, HDMA and the speed switch
+    pub fn enable_cgb(&mut self) {
+        self.cgb.enabled = true;
+    }
+
+    /// True while a VRAM DMA keeps the CPU halted, the rest of the system should keep ticking
+    pub fn is_cpu_stalled(&self) -> bool {
+        self.stall_clocks > 0
+    }
+
+    /// PPU entered HBlank, moves the next 16 bytes of an HBlank DMA
+    pub fn hblank(&mut self) {
+        if self.cgb.hdma.is_hblank_active() {
+            self.hdma_block();
+        }
+    }
+
+    fn hdma_block(&mut self) {
+        if let Some((source, destination)) = self.cgb.hdma.next_block() {
+            for i in 0..0x10 {
+                let byte = self.read_bus(source.wrapping_add(i));
+                self.write_vram(destination + i, byte);
+            }
+            self.stall_clocks += self.cgb.hdma_block_clocks();
         }
     }
-    pub fn read_ahead(&self, at: usize) -> [u8; 4] {
-        let mut ret: [u8;4] = Default::default();
-        ret.copy_from_slice(&self.ram[at..at+4]);
-        ret
+
+    /// Maps the boot ROM over the cartridge. DMG boot ROMs cover 0x0000-0x00FF, CGB ones also cover 0x0200-0x08FF and leave the header at 0x0100 visible.
+    pub fn load_boot_rom(&mut self, boot_rom: Vec<u8>) {
+        self.boot_rom = Some(boot_rom);
+    }
+
+    pub fn is_boot_rom_mapped(&self) -> bool {
+        self.boot_rom.is_some()
     }
 
-    pub fn read_word(&self, at: usize) -> u16 { // le
-        let word = (self.ram[at] as u16) | ((self.ram[at+1] as u16) << 8);
+    fn boot_rom_byte(&self, at: u16) -> Option<u8> {
+        let boot_rom = self.boot_rom.as_ref()?;
+        let mapped = at < DMG_BOOT_ROM_SIZE || (boot_rom.len() >= CGB_BOOT_ROM_SIZE as usize && (0x200..CGB_BOOT_ROM_SIZE).contains(&at));
+        if mapped {
+            boot_rom.get(at as usize).copied()
+        } else {
+            None
+        }
+    }
+
+    pub fn insert_cartridge(&mut self, cartridge: Cartridge) {
+        self.cartridge = Some(cartridge);
+    }
+
+    /// Advances bus side hardware by `clocks` T-cycles, call it with the same clocks that were fed into the CPU
+    pub fn tick(&mut self, clocks: usize) {
+        self.clocks += clocks;
+        self.stall_clocks = self.stall_clocks.saturating_sub(clocks);
+        while self.clocks >= 4 {
+            self.clocks -= 4;
+            if let Some((source, destination)) = self.dma.step() {
+                self.ram[destination as usize] = self.read_bus(source);
+            }
+            if self.timer.step() {
+                self.interrupts.request(Interrupt::Timer);
+            }
+            if self.joypad.take_interrupt() {
+                self.interrupts.request(Interrupt::Joypad);
+            }
+            if self.serial.step() {
+                self.interrupts.request(Interrupt::Serial);
+            }
+            if self.lcd.take_interrupt() {
+                self.interrupts.request(Interrupt::Stat);
+            }
+        }
+    }
+
+    /// Input from whatever frontend, script or replay drives the emulator
+    pub fn set_buttons(&mut self, buttons: Buttons) {
+        self.joypad.set_buttons(buttons);
+        if self.joypad.take_interrupt() {
+            self.interrupts.request(Interrupt::Joypad);
+        }
+    }
+
+    fn io_device(&self, at: u16) -> &dyn IoDevice {
+        match io::owner(at) {
+            IoOwner::Timer => &self.timer,
+            IoOwner::Interrupts => &self.interrupts,
+            IoOwner::Joypad => &self.joypad,
+            IoOwner::Serial => &self.serial,
+            IoOwner::Ppu => match at {
+                io::DMA | io::BGP | io::OBP0 | io::OBP1 => &self.io,
+                _ => &self.lcd,
+            },
+            IoOwner::Apu => &self.io,
+            IoOwner::Cgb => &self.cgb,
+            IoOwner::BootRom | IoOwner::Unmapped => &self.io,
+        }
+    }
+
+    fn io_device_mut(&mut self, at: u16) -> &mut dyn IoDevice {
+        match io::owner(at) {
+            IoOwner::Timer => &mut self.timer,
+            IoOwner::Interrupts => &mut self.interrupts,
+            IoOwner::Joypad => &mut self.joypad,
+            IoOwner::Serial => &mut self.serial,
+            IoOwner::Ppu => match at {
+                io::DMA | io::BGP | io::OBP0 | io::OBP1 => &mut self.io,
+                _ => &mut self.lcd,
+            },
+            IoOwner::Apu => &mut self.io,
+            IoOwner::Cgb => &mut self.cgb,
+            IoOwner::BootRom | IoOwner::Unmapped => &mut self.io,
+        }
+    }
+
+    /// Fetches the opcode and up to three operand bytes, wrapping past 0xFFFF back to 0x0000 the same way PC does.
+    /// Fetches are reported through `watch_execute` rather than as reads.
+    pub fn read_ahead(&self, at: u16) -> [u8; 4] {
+        [0, 1, 2, 3].map(|offset| self.read_unwatched(at.wrapping_add(offset)))
+    }
+
+    pub fn read_word(&self, at: u16) -> u16 { // le
+        let word = (self.read_byte(at) as u16) | ((self.read_byte(at.wrapping_add(1)) as u16) << 8);
         word
     }
 
-    pub fn read_byte(&self, at: usize) -> u8 {
-        self.ram[at]
+    /// CPU side read, it's locked out of everything but 0xFF00-0xFFFF during OAM DMA, and out of VRAM and OAM while the PPU uses them
+    pub fn read_byte(&self, at: u16) -> u8 {
+        let byte = self.read_unwatched(at);
+        let mut watches = self.watches.borrow_mut();
+        if !watches.is_empty() {
+            watches.check(Access::READ, at, byte, byte);
+        }
+        byte
     }
 
-    pub fn write_byte(&mut self, at: usize, byte: u8) {
-        self.ram[at] = byte;
+    fn read_unwatched(&self, at: u16) -> u8 {
+        if (self.dma.blocks_bus() && at < IO_START) || self.is_locked_by_ppu(at) {
+            return 0xFF;
+        }
+        self.read_bus(at)
     }
 
-    pub fn write_word(&mut self, at: usize, word: u16) { // le
-        self.ram[at] = word as u8;
-        self.ram[at+1] = (word >> 8) as u8;
+    fn read_bus(&self, at: u16) -> u8 {
+        if let Some(byte) = self.boot_rom_byte(at) {
+            return byte;
+        }
+        match (at, &self.cartridge) {
+            (0x0000..=0x7FFF, Some(cartridge)) => cartridge.read_rom(at),
+            (0xA000..=0xBFFF, Some(cartridge)) => cartridge.read_ram(at),
+            (VRAM_START..=VRAM_END, _) => self.read_vram(at),
+            (0xD000..=0xDFFF, _) => match self.wram_bank_offset(at) {
+                Some(offset) => self.wram_banks[offset],
+                None => self.ram[at as usize],
+            },
+            (IO_START..=IO_END, _) => self.io_device(at).read_io(at),
+            (io::IE, _) => self.interrupts.read_io(at),
+            _ => self.ram[at as usize],
+        }
+    }
+
+    fn read_vram(&self, at: u16) -> u8 {
+        match (self.cgb.vram_bank(), at) {
+            (0, VRAM_START..=TILE_DATA_END) => self.cram.read(at),
+            (0, BG_MAP_1_START..=0x9BFF) => self.bgmapdata1.read(at - BG_MAP_1_START),
+            (0, _) => self.bgmapdata2.read(at - BG_MAP_2_START),
+            (_, VRAM_START..=TILE_DATA_END) => self.cram_bank1.read(at),
+            (_, BG_MAP_1_START..=0x9BFF) => self.bgattrdata1.read(at - BG_MAP_1_START),
+            (_, _) => self.bgattrdata2.read(at - BG_MAP_2_START),
+        }
+    }
+
+    fn write_vram(&mut self, at: u16, byte: u8) {
+        match (self.cgb.vram_bank(), at) {
+            (0, VRAM_START..=TILE_DATA_END) => self.cram.write(at, byte),
+            (0, BG_MAP_1_START..=0x9BFF) => self.bgmapdata1.write(at - BG_MAP_1_START, byte),
+            (0, _) => self.bgmapdata2.write(at - BG_MAP_2_START, byte),
+            (_, VRAM_START..=TILE_DATA_END) => self.cram_bank1.write(at, byte),
+            (_, BG_MAP_1_START..=0x9BFF) => self.bgattrdata1.write(at - BG_MAP_1_START, byte),
+            (_, _) => self.bgattrdata2.write(at - BG_MAP_2_START, byte),
+        }
+    }
+
+    /// Offset into the extra WRAM banks, None when 0xD000-0xDFFF shows bank 1 which lives in ram
+    fn wram_bank_offset(&self, at: u16) -> Option<usize> {
+        match self.cgb.wram_bank() {
+            1 => None,
+            bank => Some((bank - 2) * WRAM_BANK_SIZE + (at as usize - 0xD000)),
+        }
+    }
+
+    pub fn write_byte(&mut self, at: u16, byte: u8) {
+        if (self.dma.blocks_bus() && at < IO_START) || self.is_locked_by_ppu(at) {
+            return;
+        }
+        if !self.watches.get_mut().is_empty() {
+            let old = self.read_bus(at);
+            self.watches.get_mut().check(Access::WRITE, at, old, byte);
+        }
+        if at == BOOT_ROM_DISABLE && byte != 0 {
+            self.boot_rom = None;
+        }
+        if at == io::DMA {
+            self.dma.start(byte);
+        }
+        if at == cgb::HDMA5 {
+            if self.cgb.enabled && self.cgb.start_hdma(byte) {
+                while self.cgb.hdma.next_block_pending() {
+                    self.hdma_block();
+                }
+            }
+            return;
+        }
+        match (at, &mut self.cartridge) {
+            (0x0000..=0x7FFF, Some(cartridge)) => cartridge.write_rom(at, byte),
+            (0xA000..=0xBFFF, Some(cartridge)) => cartridge.write_ram(at, byte),
+            (VRAM_START..=VRAM_END, _) => self.write_vram(at, byte),
+            (0xD000..=0xDFFF, _) => match self.wram_bank_offset(at) {
+                Some(offset) => self.wram_banks[offset] = byte,
+                None => self.ram[at as usize] = byte,
+            },
+            (IO_START..=IO_END, _) => self.io_device_mut(at).write_io(at, byte),
+            (io::IE, _) => self.interrupts.write_io(at, byte),
+            _ => self.ram[at as usize] = byte,
+        }
+    }
+
+    pub fn write_word(&mut self, at: u16, word: u16) { // le
+        self.write_byte(at, word as u8);
+        self.write_byte(at.wrapping_add(1), (word >> 8) as u8);
     }
 
     pub fn initialize<I>(&mut self, iter: I)
         where I: IntoIterator<Item=u8> {
         for (i, byte) in iter.into_iter().enumerate() {
-            self.write_byte(i, byte);
+            self.write_byte(i as u16, byte);
         }
     }
 }
 
 
+
+#[cfg(test)]
+mod tests;
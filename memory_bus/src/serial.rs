@@ -0,0 +1,166 @@
+use std::cell::RefCell;
+use std::io::Write;
+use std::rc::Rc;
+
+use crate::io::{IoDevice, SB, SC};
+
+/// M-cycles per bit with the internal 8192 Hz clock
+const BIT_CYCLES: u16 = 128;
+
+/// Whatever sits at the other end of the link cable
+pub trait SerialEndpoint {
+    /// Called when this side finishes a transfer on its own clock, returns the byte the other side shifted back
+    fn exchange(&mut self, byte: u8) -> u8;
+
+    /// Polled every M-cycle while this side waits for an external clock, `Some` completes the transfer with the
+    /// received byte. Nothing clocks a lone Game Boy, so by default such a transfer never ends.
+    fn poll_external(&mut self, _byte: u8) -> Option<u8> {
+        None
+    }
+
+    /// Called every M-cycle, transfer or not, for endpoints that need to keep time
+    fn tick(&mut self) {}
+}
+
+/// No cable, the input line floats high
+#[derive(Debug, Default)]
+pub struct Disconnected;
+
+impl SerialEndpoint for Disconnected {
+    fn exchange(&mut self, _byte: u8) -> u8 {
+        0xFF
+    }
+}
+
+/// Records every byte sent, which is how test ROMs like Blargg's report results. Clones share the same buffer, so keep
+/// one around to read the output after handing the other to the MMU.
+#[derive(Debug, Default, Clone)]
+pub struct Capture {
+    output: Rc<RefCell<Vec<u8>>>,
+    stdout: bool,
+}
+
+impl Capture {
+    /// Also echoes every byte to stdout as it arrives
+    pub fn stdout() -> Capture {
+        Capture { stdout: true, ..Capture::default() }
+    }
+
+    pub fn bytes(&self) -> Vec<u8> {
+        self.output.borrow().clone()
+    }
+
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.output.borrow()).into_owned()
+    }
+}
+
+impl SerialEndpoint for Capture {
+    fn exchange(&mut self, byte: u8) -> u8 {
+        self.output.borrow_mut().push(byte);
+        if self.stdout {
+            let mut stdout = std::io::stdout();
+            let _ = stdout.write_all(&[byte]).and_then(|_| stdout.flush());
+        }
+        0xFF
+    }
+}
+
+/// Output wired straight back to input
+#[derive(Debug, Default)]
+pub struct Loopback;
+
+impl SerialEndpoint for Loopback {
+    fn exchange(&mut self, byte: u8) -> u8 {
+        byte
+    }
+}
+
+/// SB and SC. Setting bit 7 of SC starts a transfer, with bit 0 set this side drives the clock at 8192 Hz and the byte
+/// is shifted out over 8 bit periods, otherwise it waits for the endpoint to clock it. Completion requests the serial
+/// interrupt.
+pub struct Serial {
+    data: u8,
+    control: u8,
+    // SB as it was when the transfer started, SB itself shifts while the transfer runs
+    outgoing: u8,
+    // M-cycles into the current transfer
+    cycles: u16,
+    endpoint: Box<dyn SerialEndpoint>,
+}
+
+impl Default for Serial {
+    fn default() -> Serial {
+        Serial { data: 0, control: 0, outgoing: 0, cycles: 0, endpoint: Box::new(Disconnected) }
+    }
+}
+
+impl Serial {
+    pub fn connect(&mut self, endpoint: Box<dyn SerialEndpoint>) {
+        self.endpoint = endpoint;
+    }
+
+    pub fn is_transferring(&self) -> bool {
+        self.control & 0x80 > 0
+    }
+
+    fn internal_clock(&self) -> bool {
+        self.control & 0x01 > 0
+    }
+
+    fn finish(&mut self, received: u8) {
+        self.data = received;
+        self.control &= 0x7F;
+        self.cycles = 0;
+    }
+
+    /// Advances one M-cycle, returns true when the serial interrupt should be requested
+    pub fn step(&mut self) -> bool {
+        self.endpoint.tick();
+        if !self.is_transferring() {
+            return false;
+        }
+        if !self.internal_clock() {
+            return match self.endpoint.poll_external(self.outgoing) {
+                Some(received) => {
+                    self.finish(received);
+                    true
+                }
+                None => false,
+            };
+        }
+        self.cycles += 1;
+        if self.cycles.is_multiple_of(BIT_CYCLES) {
+            // the incoming bits aren't known until the exchange, the idle level gets shifted in meanwhile
+            self.data = self.data << 1 | 0x01;
+        }
+        if self.cycles == 8 * BIT_CYCLES {
+            let received = self.endpoint.exchange(self.outgoing);
+            self.finish(received);
+            return true;
+        }
+        false
+    }
+}
+
+impl IoDevice for Serial {
+    fn read_io(&self, at: u16) -> u8 {
+        match at {
+            SB => self.data,
+            SC => 0x7E | self.control,
+            _ => 0xFF,
+        }
+    }
+
+    fn write_io(&mut self, at: u16, byte: u8) {
+        match at {
+            SB => self.data = byte,
+            SC => {
+                self.control = byte & 0x81;
+                self.outgoing = self.data;
+                self.cycles = 0;
+            }
+            _ => {}
+        }
+    }
+}
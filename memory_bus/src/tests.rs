@@ -0,0 +1,506 @@
+use super::*;
+use cartridge::{Cartridge, ROM_BANK_SIZE};
+
+fn prerequisites() -> MMU {
+    let mut rom = vec![0xCA; 2 * ROM_BANK_SIZE];
+    rom[0x147] = 0x00; // no mapper
+    rom[0x149] = 0x00; // no RAM
+    let mut mmu = MMU::new();
+    mmu.insert_cartridge(Cartridge::new(rom).unwrap());
+    mmu
+}
+
+#[test]
+fn boot_rom_overlay_test() {
+    let mut mmu = prerequisites();
+    mmu.load_boot_rom(vec![0xB0; 0x100]);
+    assert_eq!(mmu.read_byte(0x0000), 0xB0);
+    assert_eq!(mmu.read_byte(0x00FF), 0xB0);
+    assert_eq!(mmu.read_byte(0x0100), 0xCA);
+    mmu.write_byte(BOOT_ROM_DISABLE, 0x00);
+    assert_eq!(mmu.read_byte(0x0000), 0xB0);
+    mmu.write_byte(BOOT_ROM_DISABLE, 0x01);
+    assert_eq!(mmu.read_byte(0x0000), 0xCA);
+    assert!(!mmu.is_boot_rom_mapped());
+}
+
+#[test]
+fn cgb_boot_rom_overlay_test() {
+    let mut mmu = prerequisites();
+    mmu.load_boot_rom(vec![0xB0; 0x900]);
+    assert_eq!(mmu.read_byte(0x00FF), 0xB0);
+    assert_eq!(mmu.read_byte(0x0100), 0xCA);
+    assert_eq!(mmu.read_byte(0x01FF), 0xCA);
+    assert_eq!(mmu.read_byte(0x0200), 0xB0);
+    assert_eq!(mmu.read_byte(0x08FF), 0xB0);
+    assert_eq!(mmu.read_byte(0x0900), 0xCA);
+}
+
+#[test]
+fn io_register_masks_test() {
+    let mut mmu = MMU::new();
+    assert_eq!(mmu.read_byte(0xFF10), 0x80); // NR10
+    mmu.write_byte(0xFF10, 0xFF);
+    assert_eq!(mmu.read_byte(0xFF10), 0xFF);
+    assert_eq!(mmu.io.get(0xFF10), 0x7F);
+
+    assert_eq!(mmu.read_byte(0xFF03), 0xFF);
+    mmu.write_byte(0xFF03, 0x00);
+    assert_eq!(mmu.read_byte(0xFF03), 0xFF);
+
+    mmu.lcd.set_ly(0x90);
+    mmu.write_byte(io::LY, 0x00);
+    assert_eq!(mmu.read_byte(io::LY), 0x90);
+
+    mmu.set_ppu_mode(PPUMode::ScanlineOAM);
+    mmu.write_byte(io::STAT, 0xFF);
+    assert_eq!(mmu.read_byte(io::STAT), 0xFA);
+    mmu.set_ppu_mode(PPUMode::HBlank);
+
+    mmu.write_byte(0xFF11, 0x95); // NR11, length is write only
+    assert_eq!(mmu.read_byte(0xFF11), 0xBF);
+    assert_eq!(mmu.io.get(0xFF11), 0x95);
+
+    assert_eq!(io::owner(io::DIV), IoOwner::Timer);
+    assert_eq!(io::owner(io::STAT), IoOwner::Ppu);
+    assert_eq!(io::owner(0xFF7F), IoOwner::Unmapped);
+}
+
+#[test]
+fn oam_dma_test() {
+    let mut mmu = MMU::new();
+    for i in 0..0xA0 {
+        mmu.write_byte(0xC100 + i, i as u8);
+    }
+    mmu.write_byte(io::DMA, 0xC1);
+    assert_eq!(mmu.read_byte(io::DMA), 0xC1);
+    assert_eq!(mmu.read_byte(0xC100), 0x00); // bus is still free until the setup cycle passes
+    mmu.tick(4);
+    assert_eq!(mmu.read_byte(0xC101), 0xFF);
+    mmu.write_byte(0xFF80, 0x42);
+    assert_eq!(mmu.read_byte(0xFF80), 0x42);
+    mmu.write_byte(0xC000, 0x42);
+    mmu.tick(4 * 159);
+    assert_eq!(mmu.read_byte(0xC000), 0xFF);
+    mmu.tick(2);
+    assert_eq!(mmu.read_byte(0xC000), 0xFF);
+    mmu.tick(2);
+    assert_eq!(mmu.read_byte(0xC000), 0x00);
+    assert_eq!(mmu.read_byte(0xFE9F), 0x9F);
+    assert_eq!((0..0xA0).map(|i| mmu.read_byte(0xFE00 + i)).collect::<Vec<_>>(), (0..0xA0).collect::<Vec<u8>>());
+}
+
+/// Small xorshift so the whole address range tests are reproducible without pulling in a proptest dependency
+fn values(seed: u32) -> impl Iterator<Item = u8> {
+    let mut state = seed;
+    std::iter::repeat_with(move || {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        state as u8
+    })
+}
+
+#[test]
+fn read_ahead_wraps_test() {
+    let mut mmu = MMU::new();
+    for (at, byte) in (0..=0xFFFF_u16).zip(values(0x1234_5678)) {
+        mmu.ram[at as usize] = byte;
+    }
+    for at in 0..=0xFFFF_u16 {
+        let expected = [0, 1, 2, 3].map(|offset| mmu.read_byte(at.wrapping_add(offset)));
+        assert_eq!(mmu.read_ahead(at), expected, "read_ahead at {:#06X}", at);
+    }
+    mmu.write_byte(io::IE, 0xAB); // 0xFFFF is IE now, not plain ram
+    mmu.ram[0x0000] = 0xCD;
+    assert_eq!(mmu.read_word(0xFFFF), 0xCDAB);
+}
+
+#[test]
+fn byte_round_trip_test() {
+    let mut mmu = MMU::new();
+    for (at, byte) in (0..=0xFFFF_u16).zip(values(0x9E37_79B9)) {
+        mmu.write_byte(at, byte);
+        let expected = if at == io::P1 {
+            0xCF | byte & 0x30 // released buttons read 1
+        } else if at == io::STAT {
+            0x84 | byte & 0x78 // LY and LYC are both still 0
+        } else if (IO_START..=IO_END).contains(&at) {
+            io::spec(at).read(io::spec(at).write(0, byte))
+        } else {
+            byte
+        };
+        assert_eq!(mmu.read_byte(at), expected, "byte at {:#06X}", at);
+    }
+}
+
+#[test]
+fn word_round_trip_test() {
+    let mut mmu = MMU::new();
+    let words = values(0x0BAD_F00D).zip(values(0xDEAD_BEEF)).map(|(l, h)| (h as u16) << 8 | l as u16);
+    for (at, word) in (0..=0xFFFF_u16).zip(words) {
+        let high = at.wrapping_add(1);
+        if (IO_START..=IO_END).contains(&at) || (IO_START..=IO_END).contains(&high) {
+            continue;
+        }
+        mmu.write_word(at, word);
+        assert_eq!(mmu.read_word(at), word, "word at {:#06X}", at);
+    }
+    mmu.write_word(0xFFFF, 0x1234);
+    assert_eq!(mmu.read_byte(0xFFFF), 0x34);
+    assert_eq!(mmu.read_byte(0x0000), 0x12);
+}
+
+#[test]
+fn character_ram_test() {
+    let mut mmu = MMU::new();
+    // tile 1, row 0: low plane 0b1010_0101, high plane 0b1100_0011
+    mmu.write_byte(0x8010, 0xA5);
+    mmu.write_byte(0x8011, 0xC3);
+    assert_eq!(mmu.cram.tile(1)[0], [3, 2, 1, 0, 0, 1, 2, 3]);
+    assert_eq!(mmu.read_byte(0x8010), 0xA5);
+
+    mmu.write_byte(0x801F, 0xFF);
+    assert_eq!(mmu.cram.tile(1)[7], [2; 8]);
+    assert_eq!(mmu.cram.tile(1)[0], [3, 2, 1, 0, 0, 1, 2, 3]);
+    assert_eq!(mmu.cram.tile(0), &[[0; 8]; 8]);
+
+    mmu.write_byte(0x97FE, 0xFF);
+    assert_eq!(mmu.cram.tile(vram::TILE_COUNT - 1)[7], [1; 8]);
+}
+
+#[test]
+fn bg_map_test() {
+    use vram::TileAddressing;
+
+    let mut mmu = MMU::new();
+    mmu.write_byte(0x9800 + 32 + 2, 0x80);
+    mmu.write_byte(0x9C00 + 31 * 32 + 31, 0x7F);
+    assert_eq!(mmu.bgmapdata1.tile_index(2, 1), 0x80);
+    assert_eq!(mmu.bgmapdata1.tile_number(2, 1, TileAddressing::Unsigned), 0x80);
+    assert_eq!(mmu.bgmapdata1.tile_number(2, 1, TileAddressing::Signed), 128);
+    assert_eq!(mmu.bgmapdata2.tile_number(31, 31, TileAddressing::Signed), 383);
+    assert_eq!(mmu.bgmapdata2.tile_index(63, 63), 0x7F);
+    assert_eq!(TileAddressing::from_lcdc(0x91), TileAddressing::Unsigned);
+    assert_eq!(TileAddressing::Signed.tile_number(0x00), 256);
+}
+
+#[test]
+fn cgb_banking_test() {
+    let mut mmu = MMU::new();
+    assert_eq!(mmu.read_byte(cgb::VBK), 0xFF);
+    mmu.write_byte(cgb::SVBK, 0x03);
+    mmu.write_byte(0xD000, 0x11);
+    assert_eq!(mmu.ram[0xD000], 0x11); // DMG has no banking
+
+    mmu.enable_cgb();
+    mmu.write_byte(cgb::VBK, 0x01);
+    assert_eq!(mmu.read_byte(cgb::VBK), 0xFF);
+    mmu.write_byte(0x8000, 0xAA);
+    mmu.write_byte(0x9800, 0x08);
+    mmu.write_byte(cgb::VBK, 0x00);
+    assert_eq!(mmu.read_byte(cgb::VBK), 0xFE);
+    assert_eq!(mmu.read_byte(0x8000), 0x00);
+    assert_eq!(mmu.read_byte(0x9800), 0x00);
+    assert_eq!(mmu.cram_bank1.read(0x8000), 0xAA);
+    assert_eq!(mmu.bgattrdata1.tile_index(0, 0), 0x08);
+
+    mmu.write_byte(cgb::SVBK, 0x07);
+    assert_eq!(mmu.read_byte(cgb::SVBK), 0xFF);
+    mmu.write_byte(0xD123, 0x77);
+    mmu.write_byte(0xC123, 0x66);
+    mmu.write_byte(cgb::SVBK, 0x00); // bank 0 selects bank 1
+    assert_eq!(mmu.read_byte(0xD123), 0x00);
+    assert_eq!(mmu.read_byte(0xC123), 0x66);
+    mmu.write_byte(cgb::SVBK, 0x07);
+    assert_eq!(mmu.read_byte(0xD123), 0x77);
+}
+
+fn hdma_prerequisites() -> MMU {
+    let mut mmu = MMU::new();
+    mmu.enable_cgb();
+    for i in 0..0x40 {
+        mmu.write_byte(0xC000 + i, i as u8 + 1);
+    }
+    mmu.write_byte(cgb::HDMA1, 0xC0);
+    mmu.write_byte(cgb::HDMA2, 0x0F); // low nibble is ignored
+    mmu.write_byte(cgb::HDMA3, 0xE1); // upper bits are ignored, lands at 0x8100
+    mmu.write_byte(cgb::HDMA4, 0x00);
+    mmu
+}
+
+#[test]
+fn general_purpose_dma_test() {
+    let mut mmu = hdma_prerequisites();
+    mmu.write_byte(cgb::HDMA5, 0x01);
+    assert_eq!(mmu.read_byte(0x8100), 0x01);
+    assert_eq!(mmu.read_byte(0x811F), 0x20);
+    assert_eq!(mmu.read_byte(0x8120), 0x00);
+    assert_eq!(mmu.read_byte(cgb::HDMA5), 0xFF);
+    assert!(mmu.is_cpu_stalled());
+    mmu.tick(63);
+    assert!(mmu.is_cpu_stalled());
+    mmu.tick(1);
+    assert!(!mmu.is_cpu_stalled());
+}
+
+#[test]
+fn hblank_dma_test() {
+    let mut mmu = hdma_prerequisites();
+    mmu.write_byte(cgb::HDMA5, 0x82);
+    assert_eq!(mmu.read_byte(cgb::HDMA5), 0x02);
+    assert_eq!(mmu.read_byte(0x8100), 0x00);
+    assert!(!mmu.is_cpu_stalled());
+
+    mmu.hblank();
+    assert_eq!(mmu.read_byte(0x810F), 0x10);
+    assert_eq!(mmu.read_byte(0x8110), 0x00);
+    assert_eq!(mmu.read_byte(cgb::HDMA5), 0x01);
+    assert!(mmu.is_cpu_stalled());
+    mmu.tick(32);
+
+    mmu.write_byte(cgb::HDMA5, 0x00); // cancel
+    assert_eq!(mmu.read_byte(cgb::HDMA5), 0x81);
+    mmu.hblank();
+    assert_eq!(mmu.read_byte(0x8110), 0x00);
+}
+
+#[test]
+fn speed_switch_test() {
+    let mut mmu = MMU::new();
+    mmu.enable_cgb();
+    assert_eq!(mmu.read_byte(cgb::KEY1), 0x7E);
+    assert!(!mmu.cgb.perform_speed_switch());
+    mmu.write_byte(cgb::KEY1, 0x01);
+    assert_eq!(mmu.read_byte(cgb::KEY1), 0x7F);
+    assert!(mmu.cgb.perform_speed_switch());
+    assert_eq!(mmu.read_byte(cgb::KEY1), 0xFE);
+    assert!(mmu.cgb.is_double_speed());
+}
+
+#[test]
+fn ppu_mode_lock_test() {
+    let mut mmu = MMU::new();
+    mmu.write_byte(0x8000, 0x12);
+    mmu.write_byte(0xFE00, 0x34);
+
+    mmu.set_ppu_mode(PPUMode::ScanlineOAM);
+    assert_eq!(mmu.read_byte(0x8000), 0x12);
+    assert_eq!(mmu.read_byte(0xFE00), 0xFF);
+    mmu.write_byte(0xFE00, 0x00);
+
+    mmu.set_ppu_mode(PPUMode::ScanlineVRAM);
+    assert_eq!(mmu.read_byte(0x8000), 0xFF);
+    assert_eq!(mmu.read_byte(0xFE9F), 0xFF);
+    assert_eq!(mmu.read_byte(0xFEA0), 0x00);
+    mmu.write_byte(0x8000, 0x00);
+
+    mmu.accuracy = access::Accuracy::Lenient;
+    assert_eq!(mmu.read_byte(0x8000), 0x12);
+    assert_eq!(mmu.read_byte(0xFE00), 0x34);
+
+    mmu.accuracy = access::Accuracy::Strict;
+    mmu.set_ppu_mode(PPUMode::HBlank);
+    assert_eq!(mmu.read_byte(0x8000), 0x12);
+    assert_eq!(mmu.read_byte(0xFE00), 0x34);
+}
+
+#[test]
+fn watchpoint_test() {
+    use watch::{Access, WatchAction, Watchpoint};
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    let mut mmu = MMU::new();
+    mmu.write_byte(0xC000, 0x10);
+    let id = mmu.add_watchpoint(Watchpoint::new(0xC000..=0xC0FF, Access::WRITE).with_value(0x00));
+    mmu.set_pc(0x0150);
+    mmu.write_byte(0xC000, 0x05);
+    assert_eq!(mmu.take_watch_hit(), None);
+    mmu.write_byte(0xC000, 0x00);
+    let hit = mmu.take_watch_hit().unwrap();
+    assert_eq!((hit.id, hit.address, hit.pc, hit.old, hit.new), (id, 0xC000, 0x0150, 0x05, 0x00));
+    assert_eq!(hit.access, Access::WRITE);
+    assert_eq!(mmu.take_watch_hit(), None);
+    mmu.remove_watchpoint(id);
+    mmu.write_byte(0xC000, 0x00);
+    assert_eq!(mmu.take_watch_hit(), None);
+
+    let reads = Rc::new(Cell::new(0));
+    let counter = reads.clone();
+    mmu.add_watchpoint(Watchpoint::new(0xFF80..=0xFF80, Access::READ | Access::WRITE).with_hook(move |_| {
+        counter.set(counter.get() + 1);
+        WatchAction::Continue
+    }));
+    mmu.read_byte(0xFF80);
+    mmu.write_byte(0xFF80, 0x01);
+    mmu.read_byte(0xFF81);
+    assert_eq!(reads.get(), 2);
+    assert_eq!(mmu.take_watch_hit(), None);
+
+    mmu.add_watchpoint(Watchpoint::new(0x0100..=0x0100, Access::EXECUTE));
+    mmu.read_ahead(0x0100);
+    assert_eq!(mmu.take_watch_hit(), None);
+    mmu.watch_execute(0x0100);
+    assert_eq!(mmu.take_watch_hit().map(|hit| hit.pc), Some(0x0100));
+}
+
+#[test]
+fn timer_test() {
+    let mut mmu = MMU::new();
+    mmu.tick(256 * 3);
+    assert_eq!(mmu.read_byte(io::DIV), 3);
+    mmu.write_byte(io::DIV, 0x12);
+    assert_eq!(mmu.read_byte(io::DIV), 0x00);
+    assert_eq!(mmu.read_byte(io::TAC), 0xF8);
+
+    mmu.write_byte(io::TMA, 0xFE);
+    mmu.write_byte(io::TIMA, 0xFE);
+    mmu.write_byte(io::TAC, 0x05); // 16 T-cycles per increment
+    mmu.tick(16);
+    assert_eq!(mmu.read_byte(io::TIMA), 0xFF);
+    mmu.tick(16);
+    assert_eq!(mmu.read_byte(io::TIMA), 0x00); // overflow, reload is a cycle late
+    assert!(!mmu.interrupts.is_requested(Interrupt::Timer));
+    mmu.tick(4);
+    assert_eq!(mmu.read_byte(io::TIMA), 0xFE);
+    assert!(mmu.interrupts.is_requested(Interrupt::Timer));
+}
+
+#[test]
+fn timer_overflow_write_test() {
+    let mut mmu = MMU::new();
+    mmu.write_byte(io::TIMA, 0xFF);
+    mmu.write_byte(io::TMA, 0x20);
+    mmu.write_byte(io::TAC, 0x05);
+    mmu.tick(16);
+    mmu.write_byte(io::TIMA, 0x42); // lands in the overflow cycle, reload never happens
+    mmu.tick(4);
+    assert_eq!(mmu.read_byte(io::TIMA), 0x42);
+    assert!(!mmu.interrupts.is_requested(Interrupt::Timer));
+
+    mmu.write_byte(io::TIMA, 0xFF);
+    mmu.tick(12);
+    mmu.tick(4);
+    mmu.write_byte(io::TIMA, 0x42); // lands in the reload cycle, ignored
+    assert_eq!(mmu.read_byte(io::TIMA), 0x20);
+    mmu.write_byte(io::TMA, 0x30); // but TMA writes go straight through
+    assert_eq!(mmu.read_byte(io::TIMA), 0x30);
+}
+
+#[test]
+fn timer_glitch_test() {
+    let mut mmu = MMU::new();
+    mmu.write_byte(io::TAC, 0x05);
+    mmu.tick(8); // bit 3 of the counter is set now
+    mmu.write_byte(io::DIV, 0x00);
+    assert_eq!(mmu.read_byte(io::TIMA), 0x01);
+    mmu.tick(8);
+    mmu.write_byte(io::TAC, 0x00); // disabling while the bit is high is a falling edge too
+    assert_eq!(mmu.read_byte(io::TIMA), 0x02);
+    mmu.write_byte(io::TAC, 0x04); // bit 9 is low, enabling doesn't count
+    assert_eq!(mmu.read_byte(io::TIMA), 0x02);
+}
+
+#[test]
+fn interrupts_test() {
+    let mut mmu = MMU::new();
+    assert_eq!(mmu.read_byte(io::IF), 0xE0);
+    mmu.write_byte(io::IF, 0xFF);
+    assert_eq!(mmu.read_byte(io::IF), 0xFF);
+    assert_eq!(mmu.interrupts.pending(), None); // nothing enabled
+
+    mmu.write_byte(io::IE, 0xFF);
+    assert_eq!(mmu.read_byte(io::IE), 0xFF);
+    assert_eq!(mmu.interrupts.pending(), Some(Interrupt::VBlank));
+    mmu.write_byte(io::IF, 0x00);
+    mmu.interrupts.request(Interrupt::Joypad);
+    mmu.interrupts.request(Interrupt::Timer);
+    assert_eq!(mmu.read_byte(io::IF), 0xF4);
+    assert_eq!(mmu.interrupts.pending(), Some(Interrupt::Timer));
+    assert_eq!(Interrupt::Timer.vector(), 0x50);
+    mmu.interrupts.clear(Interrupt::Timer);
+    assert_eq!(mmu.interrupts.pending(), Some(Interrupt::Joypad));
+    mmu.write_byte(io::IE, 0x0F);
+    assert!(!mmu.interrupts.has_pending());
+}
+
+#[test]
+fn joypad_test() {
+    use joypad::Buttons;
+
+    let mut mmu = MMU::new();
+    assert_eq!(mmu.read_byte(io::P1), 0xFF);
+    mmu.set_buttons(Buttons::A | Buttons::DOWN);
+    assert_eq!(mmu.read_byte(io::P1), 0xFF); // nothing selected
+    assert!(!mmu.interrupts.is_requested(Interrupt::Joypad));
+
+    mmu.write_byte(io::P1, 0x20); // directions
+    assert_eq!(mmu.read_byte(io::P1), 0xE7);
+    mmu.tick(4);
+    assert!(mmu.interrupts.is_requested(Interrupt::Joypad)); // selecting a line with a pressed button is a falling edge
+    mmu.interrupts.clear(Interrupt::Joypad);
+
+    mmu.write_byte(io::P1, 0x10); // actions
+    assert_eq!(mmu.read_byte(io::P1), 0xDE);
+    mmu.set_buttons(Buttons::A | Buttons::START);
+    assert_eq!(mmu.read_byte(io::P1), 0xD6);
+    assert!(mmu.interrupts.is_requested(Interrupt::Joypad));
+    mmu.interrupts.clear(Interrupt::Joypad);
+
+    mmu.set_buttons(Buttons::START); // releasing is a rising edge
+    assert!(!mmu.interrupts.is_requested(Interrupt::Joypad));
+    mmu.write_byte(io::P1, 0x00);
+    assert_eq!(mmu.read_byte(io::P1), 0xC7);
+}
+
+/// Sends a byte the way Blargg's test ROMs do and waits for the transfer to end
+fn serial_send(mmu: &mut MMU, byte: u8) {
+    mmu.write_byte(io::SB, byte);
+    mmu.write_byte(io::SC, 0x81);
+    while mmu.read_byte(io::SC) & 0x80 > 0 {
+        mmu.tick(4);
+    }
+}
+
+#[test]
+fn serial_capture_test() {
+    use serial::Capture;
+
+    let mut mmu = MMU::new();
+    let capture = Capture::default();
+    mmu.serial.connect(Box::new(capture.clone()));
+    for byte in b"cpu_instrs\n\nPassed\n" {
+        serial_send(&mut mmu, *byte);
+        assert_eq!(mmu.read_byte(io::SB), 0xFF);
+    }
+    assert!(capture.text().contains("Passed"));
+}
+
+#[test]
+fn serial_transfer_test() {
+    use serial::Loopback;
+
+    let mut mmu = MMU::new();
+    assert_eq!(mmu.read_byte(io::SC), 0x7E);
+    mmu.write_byte(io::IE, 0xFF);
+    mmu.write_byte(io::SB, 0x35);
+    mmu.write_byte(io::SC, 0x81);
+    assert_eq!(mmu.read_byte(io::SC), 0xFF);
+    mmu.tick(512);
+    assert_eq!(mmu.read_byte(io::SB), 0x6B); // one bit out, idle line shifted in
+    mmu.tick(512 * 7 - 4);
+    assert_eq!(mmu.interrupts.pending(), None);
+    mmu.tick(4); // 8192 Hz, 4096 T-cycles for the whole byte
+    assert_eq!(mmu.read_byte(io::SB), 0xFF); // disconnected
+    assert_eq!(mmu.read_byte(io::SC), 0x7F);
+    assert_eq!(mmu.interrupts.pending(), Some(Interrupt::Serial));
+
+    mmu.serial.connect(Box::new(Loopback));
+    serial_send(&mut mmu, 0x35);
+    assert_eq!(mmu.read_byte(io::SB), 0x35);
+
+    mmu.write_byte(io::SC, 0x80); // external clock, nobody drives it
+    mmu.tick(4096 * 4);
+    assert!(mmu.serial.is_transferring());
+}
@@ -0,0 +1,95 @@
+use crate::io::{IoDevice, DIV, TAC, TIMA, TMA};
+
+/// DIV, TIMA, TMA and TAC. DIV is the upper byte of a 16 bit counter ticking every T-cycle, and TIMA counts falling edges of
+/// one of its bits (ANDed with the enable bit), which is where all the well known glitches on DIV and TAC writes come from.
+#[derive(Debug, Default)]
+pub struct Timer {
+    counter: u16,
+    tima: u8,
+    tma: u8,
+    tac: u8,
+    // TIMA overflowed during the last M-cycle, it reads 0 until the reload happens on the next one
+    overflow: bool,
+    // TMA got loaded into TIMA during the last M-cycle, TIMA writes are ignored and TMA writes go through to TIMA
+    reloaded: bool,
+}
+
+impl Timer {
+    pub fn div(&self) -> u8 {
+        (self.counter >> 8) as u8
+    }
+
+    /// Value of the counter bit TIMA is watching, ANDed with the enable bit
+    fn signal(&self) -> bool {
+        let bit = match self.tac & 0x03 {
+            0 => 9,
+            1 => 3,
+            2 => 5,
+            _ => 7,
+        };
+        self.tac & 0x04 > 0 && (self.counter >> bit) & 1 > 0
+    }
+
+    fn increment(&mut self) {
+        let (tima, overflow) = self.tima.overflowing_add(1);
+        self.tima = tima;
+        self.overflow = overflow;
+    }
+
+    /// Advances one M-cycle, returns true when the timer interrupt should be requested
+    pub fn step(&mut self) -> bool {
+        let mut interrupt = false;
+        self.reloaded = false;
+        if self.overflow {
+            self.overflow = false;
+            self.tima = self.tma;
+            self.reloaded = true;
+            interrupt = true;
+        }
+        let old = self.signal();
+        self.counter = self.counter.wrapping_add(4);
+        if old && !self.signal() {
+            self.increment();
+        }
+        interrupt
+    }
+
+    /// Applies a register change and bumps TIMA if that change alone produced a falling edge
+    fn update<F: FnOnce(&mut Timer)>(&mut self, change: F) {
+        let old = self.signal();
+        change(self);
+        if old && !self.signal() {
+            self.increment();
+        }
+    }
+}
+
+impl IoDevice for Timer {
+    fn read_io(&self, at: u16) -> u8 {
+        match at {
+            DIV => self.div(),
+            TIMA => self.tima,
+            TMA => self.tma,
+            TAC => 0xF8 | self.tac,
+            _ => 0xFF,
+        }
+    }
+
+    fn write_io(&mut self, at: u16, byte: u8) {
+        match at {
+            DIV => self.update(|timer| timer.counter = 0),
+            TAC => self.update(|timer| timer.tac = byte & 0x07),
+            TIMA if !self.reloaded => {
+                self.tima = byte;
+                self.overflow = false; // writing in the overflow cycle cancels the reload and the interrupt
+            }
+            TMA => {
+                self.tma = byte;
+                if self.reloaded {
+                    self.tima = byte;
+                }
+            }
+            _ => {}
+        }
+    }
+}
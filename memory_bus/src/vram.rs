@@ -0,0 +1,127 @@
+pub const VRAM_START: u16 = 0x8000;
+pub const TILE_DATA_END: u16 = 0x97FF;
+pub const BG_MAP_1_START: u16 = 0x9800;
+pub const BG_MAP_2_START: u16 = 0x9C00;
+pub const VRAM_END: u16 = 0x9FFF;
+
+pub const TILE_COUNT: usize = 384;
+const TILE_SIZE: usize = 16;
+const MAP_SIZE: usize = 32;
+
+/// Color indices 0-3 of an 8x8 tile, before any palette is applied, indexed as [y][x]
+pub type Tile = [[u8; 8]; 8];
+
+/// Which half of character RAM tile indices from the maps point into, LCDC bit 4 picks it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TileAddressing {
+    /// 0x8000 method, indices 0-255 cover tiles 0-255
+    Unsigned,
+    /// 0x8800 method, indices are signed and relative to tile 256 at 0x9000
+    Signed,
+}
+
+impl TileAddressing {
+    pub fn from_lcdc(lcdc: u8) -> TileAddressing {
+        if lcdc & 0x10 > 0 {
+            TileAddressing::Unsigned
+        } else {
+            TileAddressing::Signed
+        }
+    }
+
+    /// Tile number in character RAM, 0-383
+    pub fn tile_number(&self, index: u8) -> usize {
+        match self {
+            TileAddressing::Unsigned => index as usize,
+            TileAddressing::Signed => (256 + (index as i8) as i16) as usize,
+        }
+    }
+}
+
+/// 0x8000-0x97FF, 384 tiles in 2bpp planar format. Decoded tiles are cached and a write only invalidates the tile it lands in.
+pub struct CharacterRAM {
+    tiles: Vec<u8>,
+    decoded: Vec<Tile>,
+    valid: Vec<bool>,
+}
+
+impl Default for CharacterRAM {
+    fn default() -> CharacterRAM {
+        CharacterRAM {
+            tiles: vec![0; TILE_COUNT * TILE_SIZE],
+            decoded: vec![[[0; 8]; 8]; TILE_COUNT],
+            valid: vec![true; TILE_COUNT],
+        }
+    }
+}
+
+impl CharacterRAM {
+    pub fn read(&self, at: u16) -> u8 {
+        self.tiles[(at - VRAM_START) as usize]
+    }
+
+    pub fn write(&mut self, at: u16, byte: u8) {
+        let offset = (at - VRAM_START) as usize;
+        self.tiles[offset] = byte;
+        self.valid[offset / TILE_SIZE] = false;
+    }
+
+    pub fn raw(&self) -> &[u8] {
+        &self.tiles
+    }
+
+    pub fn tile(&mut self, number: usize) -> &Tile {
+        if !self.valid[number] {
+            self.decoded[number] = self.decode(number);
+            self.valid[number] = true;
+        }
+        &self.decoded[number]
+    }
+
+    /// Every row is two bytes, the first holds the low bit of each pixel and the second the high bit, leftmost pixel in bit 7
+    fn decode(&self, number: usize) -> Tile {
+        let bytes = &self.tiles[number * TILE_SIZE..(number + 1) * TILE_SIZE];
+        let mut tile = [[0; 8]; 8];
+        for (y, row) in tile.iter_mut().enumerate() {
+            let (low, high) = (bytes[y * 2], bytes[y * 2 + 1]);
+            for (x, pixel) in row.iter_mut().enumerate() {
+                let bit = 7 - x;
+                *pixel = ((low >> bit) & 1) | (((high >> bit) & 1) << 1);
+            }
+        }
+        tile
+    }
+}
+
+/// One of the two 32x32 tile maps at 0x9800 and 0x9C00
+pub struct BGMapData {
+    tile_index: Vec<u8>,
+}
+
+impl Default for BGMapData {
+    fn default() -> BGMapData {
+        BGMapData {
+            tile_index: vec![0; MAP_SIZE * MAP_SIZE],
+        }
+    }
+}
+
+impl BGMapData {
+    pub fn read(&self, offset: u16) -> u8 {
+        self.tile_index[offset as usize]
+    }
+
+    pub fn write(&mut self, offset: u16, byte: u8) {
+        self.tile_index[offset as usize] = byte;
+    }
+
+    /// Raw index at map coordinates, both wrap around at 32
+    pub fn tile_index(&self, x: usize, y: usize) -> u8 {
+        self.tile_index[(y % MAP_SIZE) * MAP_SIZE + (x % MAP_SIZE)]
+    }
+
+    /// Character RAM tile number at map coordinates
+    pub fn tile_number(&self, x: usize, y: usize, addressing: TileAddressing) -> usize {
+        addressing.tile_number(self.tile_index(x, y))
+    }
+}
@@ -0,0 +1,134 @@
+use std::ops::{BitOr, RangeInclusive};
+
+/// Kinds of access a watchpoint fires on, combine with `|`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Access(u8);
+
+impl Access {
+    pub const READ: Access = Access(0b001);
+    pub const WRITE: Access = Access(0b010);
+    pub const EXECUTE: Access = Access(0b100);
+
+    pub fn contains(&self, other: Access) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for Access {
+    type Output = Access;
+
+    fn bitor(self, rhs: Access) -> Access {
+        Access(self.0 | rhs.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WatchAction {
+    Continue,
+    Stop,
+}
+
+pub type WatchId = usize;
+
+/// Everything known about an access that tripped a watchpoint. `pc` is the address of the instruction doing the access.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WatchHit {
+    pub id: WatchId,
+    pub access: Access,
+    pub address: u16,
+    pub pc: u16,
+    pub old: u8,
+    pub new: u8,
+}
+
+pub type WatchHook = Box<dyn FnMut(&WatchHit) -> WatchAction>;
+
+pub struct Watchpoint {
+    pub range: RangeInclusive<u16>,
+    pub access: Access,
+    /// Only fire when the value read, written or executed equals this
+    pub value: Option<u8>,
+    hook: Option<WatchHook>,
+}
+
+impl Watchpoint {
+    /// Stops emulation on any matching access
+    pub fn new(range: RangeInclusive<u16>, access: Access) -> Watchpoint {
+        Watchpoint {
+            range,
+            access,
+            value: None,
+            hook: None,
+        }
+    }
+
+    pub fn with_value(mut self, value: u8) -> Watchpoint {
+        self.value = Some(value);
+        self
+    }
+
+    /// Runs `hook` on every matching access instead of stopping, the hook decides whether emulation should stop after all
+    pub fn with_hook<F>(mut self, hook: F) -> Watchpoint
+        where F: FnMut(&WatchHit) -> WatchAction + 'static {
+        self.hook = Some(Box::new(hook));
+        self
+    }
+}
+
+/// Watchpoints registered on the bus. The bus reads through `&self`, so the MMU keeps this in a RefCell.
+#[derive(Default)]
+pub struct Watches {
+    watchpoints: Vec<Option<Watchpoint>>,
+    pc: u16,
+    hit: Option<WatchHit>,
+}
+
+impl Watches {
+    pub fn add(&mut self, watchpoint: Watchpoint) -> WatchId {
+        self.watchpoints.push(Some(watchpoint));
+        self.watchpoints.len() - 1
+    }
+
+    pub fn remove(&mut self, id: WatchId) {
+        if let Some(slot) = self.watchpoints.get_mut(id) {
+            *slot = None;
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.watchpoints.iter().all(Option::is_none)
+    }
+
+    pub fn set_pc(&mut self, pc: u16) {
+        self.pc = pc;
+    }
+
+    /// First hit that asked to stop since the last call, the frontend polls this after every step
+    pub fn take_hit(&mut self) -> Option<WatchHit> {
+        self.hit.take()
+    }
+
+    pub fn check(&mut self, access: Access, address: u16, old: u8, new: u8) {
+        let pc = self.pc;
+        for (id, slot) in self.watchpoints.iter_mut().enumerate() {
+            let watchpoint = match slot {
+                Some(watchpoint) => watchpoint,
+                None => continue,
+            };
+            if !watchpoint.access.contains(access) || !watchpoint.range.contains(&address) {
+                continue;
+            }
+            if watchpoint.value.is_some_and(|value| value != new) {
+                continue;
+            }
+            let hit = WatchHit { id, access, address, pc, old, new };
+            let action = match &mut watchpoint.hook {
+                Some(hook) => hook(&hit),
+                None => WatchAction::Stop,
+            };
+            if action == WatchAction::Stop && self.hit.is_none() {
+                self.hit = Some(hit);
+            }
+        }
+    }
+}
@@ -0,0 +1,286 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::Path;
+
+use memory_bus::joypad::Buttons;
+use minifb::Key;
+
+/// Emulator actions that aren't Game Boy input
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Hotkey {
+    Pause,
+    FastForward,
+    SlowMotion,
+    FrameAdvance,
+    Screenshot,
+}
+
+const BUTTON_NAMES: [(&str, Buttons); 8] = [
+    ("right", Buttons::RIGHT),
+    ("left", Buttons::LEFT),
+    ("up", Buttons::UP),
+    ("down", Buttons::DOWN),
+    ("a", Buttons::A),
+    ("b", Buttons::B),
+    ("select", Buttons::SELECT),
+    ("start", Buttons::START),
+];
+
+const HOTKEY_NAMES: [(&str, Hotkey); 5] = [
+    ("pause", Hotkey::Pause),
+    ("fast_forward", Hotkey::FastForward),
+    ("slow_motion", Hotkey::SlowMotion),
+    ("frame_advance", Hotkey::FrameAdvance),
+    ("screenshot", Hotkey::Screenshot),
+];
+
+#[derive(Debug)]
+pub enum BindingsError {
+    Io(std::io::Error),
+    Parse(toml::de::Error),
+    UnknownSection(String),
+    UnknownAction(String),
+    UnknownKey(String),
+    /// Bindings are either a key name or a list of key names
+    InvalidValue(String),
+    /// The file binds one key to both a button and a hotkey
+    Conflict(String),
+}
+
+impl fmt::Display for BindingsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BindingsError::Io(e) => write!(f, "can't read bindings: {}", e),
+            BindingsError::Parse(e) => write!(f, "can't parse bindings: {}", e),
+            BindingsError::UnknownSection(name) => write!(f, "unknown bindings section [{}]", name),
+            BindingsError::UnknownAction(name) => write!(f, "unknown button or hotkey {:?}", name),
+            BindingsError::UnknownKey(name) => write!(f, "unknown key {:?}", name),
+            BindingsError::InvalidValue(name) => write!(f, "{:?} should be a key name or a list of key names", name),
+            BindingsError::Conflict(key) => write!(f, "key {:?} is bound to both a button and a hotkey", key),
+        }
+    }
+}
+
+impl std::error::Error for BindingsError {}
+
+/// Keyboard layout of the windowed frontend. Every button and hotkey can have several keys, a config file only needs to
+/// mention the ones it changes:
+///
+/// ```toml
+/// [buttons]
+/// a = ["Z", "J"]
+/// start = "Space"
+///
+/// [hotkeys]
+/// fast_forward = "Tab"
+/// ```
+///
+/// A key is either a button or a hotkey. One the file binds takes over from a default of the other kind (Space stops
+/// being fast-forward above), binding it to both in the file is an error.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bindings {
+    pub buttons: HashMap<Key, Buttons>,
+    pub hotkeys: HashMap<Key, Hotkey>,
+}
+
+impl Default for Bindings {
+    fn default() -> Bindings {
+        let buttons = [
+            (Key::Right, Buttons::RIGHT),
+            (Key::Left, Buttons::LEFT),
+            (Key::Up, Buttons::UP),
+            (Key::Down, Buttons::DOWN),
+            (Key::Z, Buttons::A),
+            (Key::X, Buttons::B),
+            (Key::Backspace, Buttons::SELECT),
+            (Key::Enter, Buttons::START),
+        ];
+        let hotkeys = [
+            (Key::P, Hotkey::Pause),
+            (Key::Space, Hotkey::FastForward),
+            (Key::M, Hotkey::SlowMotion),
+            (Key::N, Hotkey::FrameAdvance),
+            (Key::F12, Hotkey::Screenshot),
+        ];
+        Bindings {
+            buttons: buttons.iter().copied().collect(),
+            hotkeys: hotkeys.iter().copied().collect(),
+        }
+    }
+}
+
+impl Bindings {
+    /// Defaults overridden by whatever the file at `path` binds, or just the defaults when there is no such file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Bindings, BindingsError> {
+        match std::fs::read_to_string(path) {
+            Ok(config) => Bindings::parse(&config),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Bindings::default()),
+            Err(e) => Err(BindingsError::Io(e)),
+        }
+    }
+
+    pub fn parse(config: &str) -> Result<Bindings, BindingsError> {
+        let config: toml::value::Table = toml::from_str(config).map_err(BindingsError::Parse)?;
+        let mut bindings = Bindings::default();
+        let (mut button_keys, mut hotkey_keys) = (HashSet::new(), HashSet::new());
+        for (section, actions) in &config {
+            let actions = actions.as_table().ok_or_else(|| BindingsError::UnknownSection(section.clone()))?;
+            for (action, keys) in actions {
+                let keys = parse_keys(action, keys)?;
+                match section.as_str() {
+                    "buttons" => {
+                        let button = lookup(&BUTTON_NAMES, action)?;
+                        bindings.buttons.retain(|_, bound| *bound != button);
+                        button_keys.extend(keys.iter().copied());
+                        bindings.buttons.extend(keys.into_iter().map(|key| (key, button)));
+                    }
+                    "hotkeys" => {
+                        let hotkey = lookup(&HOTKEY_NAMES, action)?;
+                        bindings.hotkeys.retain(|_, bound| *bound != hotkey);
+                        hotkey_keys.extend(keys.iter().copied());
+                        bindings.hotkeys.extend(keys.into_iter().map(|key| (key, hotkey)));
+                    }
+                    _ => return Err(BindingsError::UnknownSection(section.clone())),
+                }
+            }
+        }
+        if let Some(key) = button_keys.intersection(&hotkey_keys).next() {
+            return Err(BindingsError::Conflict(format!("{:?}", key)));
+        }
+        bindings.hotkeys.retain(|key, _| !button_keys.contains(key));
+        bindings.buttons.retain(|key, _| !hotkey_keys.contains(key));
+        Ok(bindings)
+    }
+
+    /// Buttons held according to `is_down`, which lets this be driven without an actual window
+    pub fn buttons<F: Fn(Key) -> bool>(&self, is_down: F) -> Buttons {
+        self.buttons.iter()
+            .filter(|(key, _)| is_down(**key))
+            .fold(Buttons::NONE, |buttons, (_, button)| buttons | *button)
+    }
+
+    pub fn hotkey(&self, key: Key) -> Option<Hotkey> {
+        self.hotkeys.get(&key).copied()
+    }
+}
+
+fn lookup<T: Copy>(names: &[(&str, T)], name: &str) -> Result<T, BindingsError> {
+    names.iter()
+        .find(|(candidate, _)| *candidate == name)
+        .map(|(_, value)| *value)
+        .ok_or_else(|| BindingsError::UnknownAction(name.to_string()))
+}
+
+fn parse_keys(action: &str, value: &toml::Value) -> Result<Vec<Key>, BindingsError> {
+    let invalid = || BindingsError::InvalidValue(action.to_string());
+    match value {
+        toml::Value::String(name) => Ok(vec![key_from_name(name)?]),
+        toml::Value::Array(names) => names.iter()
+            .map(|name| name.as_str().ok_or_else(invalid).and_then(key_from_name))
+            .collect(),
+        _ => Err(invalid()),
+    }
+}
+
+/// Key names are the variant names of `minifb::Key`, digits can be written without the `Key` prefix
+fn key_from_name(name: &str) -> Result<Key, BindingsError> {
+    let key = match name {
+        "0" | "Key0" => Key::Key0,
+        "1" | "Key1" => Key::Key1,
+        "2" | "Key2" => Key::Key2,
+        "3" | "Key3" => Key::Key3,
+        "4" | "Key4" => Key::Key4,
+        "5" | "Key5" => Key::Key5,
+        "6" | "Key6" => Key::Key6,
+        "7" | "Key7" => Key::Key7,
+        "8" | "Key8" => Key::Key8,
+        "9" | "Key9" => Key::Key9,
+        "A" => Key::A,
+        "B" => Key::B,
+        "C" => Key::C,
+        "D" => Key::D,
+        "E" => Key::E,
+        "F" => Key::F,
+        "G" => Key::G,
+        "H" => Key::H,
+        "I" => Key::I,
+        "J" => Key::J,
+        "K" => Key::K,
+        "L" => Key::L,
+        "M" => Key::M,
+        "N" => Key::N,
+        "O" => Key::O,
+        "P" => Key::P,
+        "Q" => Key::Q,
+        "R" => Key::R,
+        "S" => Key::S,
+        "T" => Key::T,
+        "U" => Key::U,
+        "V" => Key::V,
+        "W" => Key::W,
+        "X" => Key::X,
+        "Y" => Key::Y,
+        "Z" => Key::Z,
+        "F1" => Key::F1,
+        "F2" => Key::F2,
+        "F3" => Key::F3,
+        "F4" => Key::F4,
+        "F5" => Key::F5,
+        "F6" => Key::F6,
+        "F7" => Key::F7,
+        "F8" => Key::F8,
+        "F9" => Key::F9,
+        "F10" => Key::F10,
+        "F11" => Key::F11,
+        "F12" => Key::F12,
+        "Down" => Key::Down,
+        "Left" => Key::Left,
+        "Right" => Key::Right,
+        "Up" => Key::Up,
+        "Apostrophe" => Key::Apostrophe,
+        "Backquote" => Key::Backquote,
+        "Backslash" => Key::Backslash,
+        "Comma" => Key::Comma,
+        "Equal" => Key::Equal,
+        "LeftBracket" => Key::LeftBracket,
+        "Minus" => Key::Minus,
+        "Period" => Key::Period,
+        "RightBracket" => Key::RightBracket,
+        "Semicolon" => Key::Semicolon,
+        "Slash" => Key::Slash,
+        "Backspace" => Key::Backspace,
+        "Delete" => Key::Delete,
+        "End" => Key::End,
+        "Enter" => Key::Enter,
+        "Escape" => Key::Escape,
+        "Home" => Key::Home,
+        "Insert" => Key::Insert,
+        "PageDown" => Key::PageDown,
+        "PageUp" => Key::PageUp,
+        "Pause" => Key::Pause,
+        "Space" => Key::Space,
+        "Tab" => Key::Tab,
+        "LeftShift" => Key::LeftShift,
+        "RightShift" => Key::RightShift,
+        "LeftCtrl" => Key::LeftCtrl,
+        "RightCtrl" => Key::RightCtrl,
+        "LeftAlt" => Key::LeftAlt,
+        "RightAlt" => Key::RightAlt,
+        "NumPad0" => Key::NumPad0,
+        "NumPad1" => Key::NumPad1,
+        "NumPad2" => Key::NumPad2,
+        "NumPad3" => Key::NumPad3,
+        "NumPad4" => Key::NumPad4,
+        "NumPad5" => Key::NumPad5,
+        "NumPad6" => Key::NumPad6,
+        "NumPad7" => Key::NumPad7,
+        "NumPad8" => Key::NumPad8,
+        "NumPad9" => Key::NumPad9,
+        "NumPadEnter" => Key::NumPadEnter,
+        _ => return Err(BindingsError::UnknownKey(name.to_string())),
+    };
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests;
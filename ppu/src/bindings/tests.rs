@@ -0,0 +1,54 @@
+use super::*;
+
+#[test]
+fn default_bindings_test() {
+    let bindings = Bindings::default();
+    assert_eq!(bindings.buttons(|key| key == Key::Z || key == Key::Up), Buttons::A | Buttons::UP);
+    assert_eq!(bindings.buttons(|_| false), Buttons::NONE);
+    assert_eq!(bindings.hotkey(Key::Space), Some(Hotkey::FastForward));
+    assert_eq!(bindings.hotkey(Key::Q), None);
+    assert_eq!(Bindings::parse("").unwrap(), bindings);
+}
+
+#[test]
+fn parse_bindings_test() {
+    let bindings = Bindings::parse(r#"
+        [buttons]
+        a = ["J", "NumPad0"]
+        start = "Space"
+
+        [hotkeys]
+        fast_forward = "Tab"
+        screenshot = []
+    "#).unwrap();
+    assert_eq!(bindings.buttons(|key| key == Key::J), Buttons::A);
+    assert_eq!(bindings.buttons(|key| key == Key::NumPad0), Buttons::A);
+    assert_eq!(bindings.buttons(|key| key == Key::Z), Buttons::NONE); // replaced, not added to
+    assert_eq!(bindings.buttons(|key| key == Key::Space), Buttons::START);
+    assert_eq!(bindings.buttons(|key| key == Key::X), Buttons::B);
+    assert_eq!(bindings.hotkey(Key::Tab), Some(Hotkey::FastForward));
+    assert_eq!(bindings.hotkey(Key::Space), None);
+    assert_eq!(bindings.hotkey(Key::F12), None);
+    assert_eq!(bindings.hotkey(Key::P), Some(Hotkey::Pause));
+
+    // taking a key away from a default of the other kind
+    let bindings = Bindings::parse("[buttons]\nstart = \"Space\"").unwrap();
+    assert_eq!(bindings.buttons(|key| key == Key::Space), Buttons::START);
+    assert_eq!(bindings.hotkey(Key::Space), None);
+    let bindings = Bindings::parse("[hotkeys]\npause = \"Enter\"").unwrap();
+    assert_eq!(bindings.buttons(|key| key == Key::Enter), Buttons::NONE);
+    assert_eq!(bindings.hotkey(Key::Enter), Some(Hotkey::Pause));
+}
+
+#[test]
+fn invalid_bindings_test() {
+    let error = |config: &str| Bindings::parse(config).unwrap_err().to_string();
+    assert_eq!(error("[buttons]\nturbo = \"T\""), "unknown button or hotkey \"turbo\"");
+    assert_eq!(error("[buttons]\na = \"Hyper\""), "unknown key \"Hyper\"");
+    assert_eq!(error("[buttons]\na = 1"), "\"a\" should be a key name or a list of key names");
+    assert_eq!(error("[gamepad]\na = \"A\""), "unknown bindings section [gamepad]");
+    assert_eq!(error("[buttons]\nstart = \"Space\"\n[hotkeys]\npause = \"Space\""),
+               "key \"Space\" is bound to both a button and a hotkey");
+    assert!(Bindings::parse("[buttons").is_err());
+    assert_eq!(Bindings::load("/nonexistent/bindings.toml").unwrap(), Bindings::default());
+}
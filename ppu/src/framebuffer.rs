@@ -0,0 +1,88 @@
+use std::io::{self, Write};
+
+use crate::{SCREEN_HEIGHT, SCREEN_WIDTH};
+
+/// RGBA for shades 0 to 3
+pub type Palette = [[u8; 4]; 4];
+
+pub const GRAYSCALE: Palette = [
+    [0xFF, 0xFF, 0xFF, 0xFF],
+    [0xAA, 0xAA, 0xAA, 0xFF],
+    [0x55, 0x55, 0x55, 0xFF],
+    [0x00, 0x00, 0x00, 0xFF],
+];
+
+/// The greenish tint of the original DMG screen
+pub const DMG_GREEN: Palette = [
+    [0x9B, 0xBC, 0x0F, 0xFF],
+    [0x8B, 0xAC, 0x0F, 0xFF],
+    [0x30, 0x62, 0x30, 0xFF],
+    [0x0F, 0x38, 0x0F, 0xFF],
+];
+
+/// Shades 0 (lightest) to 3 (darkest) after the palettes, row by row. Turning them into colors is up to whoever shows
+/// the frame, so the PPU doesn't need a window to run.
+#[derive(Debug, Clone)]
+pub struct FrameBuffer {
+    shades: Box<[u8; SCREEN_WIDTH * SCREEN_HEIGHT]>,
+}
+
+impl Default for FrameBuffer {
+    fn default() -> FrameBuffer {
+        FrameBuffer { shades: Box::new([0; SCREEN_WIDTH * SCREEN_HEIGHT]) }
+    }
+}
+
+impl FrameBuffer {
+    pub fn shades(&self) -> &[u8] {
+        &self.shades[..]
+    }
+
+    pub fn shade(&self, x: usize, y: usize) -> u8 {
+        self.shades[y * SCREEN_WIDTH + x]
+    }
+
+    pub(crate) fn row_mut(&mut self, y: usize) -> &mut [u8] {
+        &mut self.shades[y * SCREEN_WIDTH..(y + 1) * SCREEN_WIDTH]
+    }
+
+    /// 4 bytes per pixel, row by row
+    pub fn to_rgba(&self, palette: &Palette) -> Vec<u8> {
+        self.shades.iter().flat_map(|shade| palette[*shade as usize].iter().copied()).collect()
+    }
+
+    /// Binary PPM, which about every image viewer and converter reads, for screenshots without an image library
+    pub fn write_ppm<W: Write>(&self, palette: &Palette, mut out: W) -> io::Result<()> {
+        write!(out, "P6\n{} {}\n255\n", SCREEN_WIDTH, SCREEN_HEIGHT)?;
+        let rgb: Vec<u8> = self.to_rgba(palette).chunks(4).flat_map(|pixel| pixel[..3].iter().copied()).collect();
+        out.write_all(&rgb)
+    }
+
+    /// Draws the frame into a `width` x `height` 0RGB buffer, nearest-neighbour scaled to the largest rectangle with
+    /// the screen's aspect ratio that fits, centered between black bars
+    pub fn blit(&self, palette: &Palette, out: &mut [u32], width: usize, height: usize) {
+        // a minimized window reports a zero size, there's nothing to draw into
+        if width == 0 || height == 0 {
+            return;
+        }
+        let (fit_width, fit_height) = if width * SCREEN_HEIGHT < height * SCREEN_WIDTH {
+            (width, width * SCREEN_HEIGHT / SCREEN_WIDTH)
+        } else {
+            (height * SCREEN_WIDTH / SCREEN_HEIGHT, height)
+        };
+        let left = (width - fit_width) / 2;
+        let top = (height - fit_height) / 2;
+        let colors = palette.map(|[r, g, b, _]| u32::from_be_bytes([0, r, g, b]));
+        for (y, row) in out.chunks_mut(width).take(height).enumerate() {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                *pixel = if (left..left + fit_width).contains(&x) && (top..top + fit_height).contains(&y) {
+                    let source_x = (x - left) * SCREEN_WIDTH / fit_width;
+                    let source_y = (y - top) * SCREEN_HEIGHT / fit_height;
+                    colors[self.shade(source_x, source_y) as usize]
+                } else {
+                    0
+                };
+            }
+        }
+    }
+}
@@ -1,101 +1,279 @@
-use minifb::{Key, WindowOptions, Window};
-use memory_bus::MMU;
+use memory_bus::{io, MMU, PPUMode};
+use memory_bus::vram::TileAddressing;
+use memory_bus::interrupts::Interrupt;
+
+#[cfg(feature = "window")]
+pub mod bindings;
+pub mod fifo;
+pub mod framebuffer;
+pub mod sprite;
+#[cfg(feature = "window")]
+pub mod window;
+
+use fifo::PixelFifo;
+pub use framebuffer::FrameBuffer;
+use sprite::Sprite;
+#[cfg(feature = "window")]
+pub use window::PPUWindow;
 
 pub type Clocks = usize;
-pub type Lines = usize;
 
+/// T-cycles spent in each mode of a visible line, and in a whole line
+const OAM_CLOCKS: Clocks = 80;
+const VRAM_CLOCKS: Clocks = 172;
+const HBLANK_CLOCKS: Clocks = 204;
+const LINE_CLOCKS: Clocks = 456;
+/// First line of VBlank and the number of lines in a frame
+const VBLANK_LINE: u8 = 144;
+const LINES: u8 = 154;
 
-#[derive(Debug)]
-enum PPUMode {
-    ScanlineOAM,
-    ScanlineVRAM,
-    HBlank,
-    VBlank,
-}
+pub const SCREEN_WIDTH: usize = 160;
+pub const SCREEN_HEIGHT: usize = 144;
 
-impl Default for PPUMode {
-    fn default() -> PPUMode {
-        PPUMode::ScanlineOAM
-    }
+/// How mode 3 turns VRAM into pixels
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub enum Renderer {
+    /// Whole line at once when mode 3 ends, which always lasts 172 dots. Cheap, and right for nearly every game.
+    #[default]
+    Scanline,
+    /// Dot by dot through the pixel FIFO, mode 3 stretches with fine scroll, the window and sprites, and
+    /// mid-scanline register writes land on the right pixel. Experimental, not validated against dmg-acid2 or
+    /// mealybug yet.
+    Fifo,
 }
 
-#[derive(Default, Debug)]
+/// Mode state machine and scanline renderer. The registers it drives (LY, STAT and friends) live in `mmu.lcd` so the
+/// CPU can reach them. Every line is drawn into `frame` when mode 3 ends, or pixel by pixel during it with the FIFO.
+#[derive(Debug)]
 pub struct PPU {
     mode: PPUMode,
     clocks: Clocks,
-    lines: Lines,
+    enabled: bool,
+    pub frame: FrameBuffer,
+    frame_ready: bool,
+    // internal window line counter, only advances on lines the window was drawn on
+    window_line: u8,
+    // color numbers of the line being drawn, before the palette
+    line: [u8; SCREEN_WIDTH],
+    // picked by the last OAM scan, in drawing priority
+    sprites: Vec<Sprite>,
+    pub renderer: Renderer,
+    fifo: Option<PixelFifo>,
+    // HBlank makes up whatever mode 3 didn't use of the line
+    hblank_clocks: Clocks,
+}
+
+impl Default for PPU {
+    fn default() -> PPU {
+        PPU {
+            mode: PPUMode::default(),
+            clocks: 0,
+            enabled: false,
+            frame: FrameBuffer::default(),
+            frame_ready: false,
+            window_line: 0,
+            line: [0; SCREEN_WIDTH],
+            sprites: Vec::new(),
+            renderer: Renderer::default(),
+            fifo: None,
+            hblank_clocks: HBLANK_CLOCKS,
+        }
+    }
 }
 
 impl PPU {
-    pub fn step(&mut self, mmu: &MMU, deltaclock: usize) {
-        self.clocks = self.clocks + deltaclock;
+    pub fn step(&mut self, mmu: &mut MMU, deltaclock: usize) {
+        if !mmu.lcd.is_enabled() {
+            // LY sits at 0 in mode 0 until the LCD comes back on, which starts a fresh frame
+            if self.enabled {
+                self.enabled = false;
+                self.window_line = 0;
+                mmu.lcd.set_ly(0);
+                mmu.set_ppu_mode(PPUMode::HBlank);
+            }
+            return;
+        }
+        if !self.enabled {
+            self.enabled = true;
+            self.mode = PPUMode::ScanlineOAM;
+            self.clocks = 0;
+        }
+        self.clocks += deltaclock;
         match self.mode {
             PPUMode::ScanlineOAM => {
-                if self.clocks >= 80 {
+                if self.clocks >= OAM_CLOCKS {
                     self.mode = PPUMode::ScanlineVRAM;
-                    self.clocks -= 80;
+                    self.clocks -= OAM_CLOCKS;
+                    self.scan_oam(mmu);
+                    if self.renderer == Renderer::Fifo {
+                        self.fifo = Some(PixelFifo::new(mmu, &self.sprites, self.window_line));
+                    }
+                }
+            }
+            PPUMode::ScanlineVRAM if self.fifo.is_some() => {
+                if self.step_fifo(mmu) {
+                    self.mode = PPUMode::HBlank;
+                    mmu.hblank();
                 }
             }
             PPUMode::ScanlineVRAM => {
-                if self.clocks >= 172 {
+                if self.clocks >= VRAM_CLOCKS {
                     self.mode = PPUMode::HBlank;
-                    self.clocks -= 172;
+                    self.clocks -= VRAM_CLOCKS;
+                    self.hblank_clocks = HBLANK_CLOCKS;
+                    self.render_scanline(mmu);
+                    mmu.hblank();
                 }
             }
             PPUMode::HBlank => {
-                if self.clocks >= 204 {
+                if self.clocks >= self.hblank_clocks {
                     self.mode = PPUMode::ScanlineOAM;
-                    self.clocks -= 204;
-                    self.lines += 1;
-                    if self.lines == 143 {
+                    self.clocks -= self.hblank_clocks;
+                    let ly = mmu.lcd.ly() + 1;
+                    if ly == VBLANK_LINE {
                         self.mode = PPUMode::VBlank;
-                        //VBLANK handling
+                        self.window_line = 0;
+                        self.frame_ready = true;
+                        mmu.interrupts.request(Interrupt::VBlank);
                     }
+                    mmu.lcd.set_ly(ly);
                 }
-
             }
             PPUMode::VBlank => {
-                if self.clocks >= 456 {
-                    self.mode = PPUMode::ScanlineOAM;
-                    self.clocks -= 456;
-                    self.lines += 1;
-                    if self.lines > 153 {
+                if self.clocks >= LINE_CLOCKS {
+                    self.clocks -= LINE_CLOCKS;
+                    let ly = mmu.lcd.ly() + 1;
+                    if ly == LINES {
                         self.mode = PPUMode::ScanlineOAM;
-                        self.lines = 0;
+                        mmu.lcd.set_ly(0);
+                    } else {
+                        mmu.lcd.set_ly(ly);
                     }
                 }
             }
         }
+        mmu.set_ppu_mode(self.mode);
     }
 
-    pub fn render_background(&mut self, mmu: &mut MMU) {
-        let cram =  &mmu.ram[0x8000..0x9800];
-        let bmap_1 = &mmu.ram[0x9C00..0xA000];
+    /// Returns true once after the last visible line of a frame was drawn
+    pub fn frame_ready(&mut self) -> bool {
+        std::mem::replace(&mut self.frame_ready, false)
     }
-}
 
-pub struct PPUWindow {
-    pub ppu: PPU,
-    buffer: Vec<u32>,
-    window: Window,
-}
+    /// Feeds the pending clocks to the pixel FIFO dot by dot, returns true when the line is done
+    fn step_fifo(&mut self, mmu: &mut MMU) -> bool {
+        let ly = mmu.lcd.ly() as usize;
+        let line = self.frame.row_mut(ly);
+        let fifo = match self.fifo.as_mut() {
+            Some(fifo) => fifo,
+            None => return false,
+        };
+        while self.clocks > 0 {
+            self.clocks -= 1;
+            if fifo.tick(mmu, line) {
+                self.hblank_clocks = LINE_CLOCKS - OAM_CLOCKS - fifo.dots;
+                if fifo.window_drawn() {
+                    self.window_line = self.window_line.wrapping_add(1);
+                }
+                self.fifo = None;
+                return true;
+            }
+        }
+        false
+    }
 
-impl PPUWindow {
-    pub fn new() -> PPUWindow {
-        let window = Window::new("Test", 640, 480, WindowOptions::default()).unwrap_or_else(|e| panic!("{}, e"));
-        let buffer: Vec<u32> = vec![0; 640 * 480];
-        let ppu = Default::default();
-        PPUWindow{ window, buffer, ppu }
-        // while window.is_open() && !window.is_key_down(Key::Escape) {
-        //     window.update_with_buffer(&buffer).unwrap();
-        // }
+    /// Draws line LY into the frame
+    pub fn render_scanline(&mut self, mmu: &mut MMU) {
+        let ly = mmu.lcd.ly() as usize;
+        self.line = [0; SCREEN_WIDTH];
+        if mmu.lcd.lcdc & 0x01 > 0 {
+            self.render_background(mmu);
+            self.render_window(mmu);
+        }
+        let bgp = mmu.io.get(io::BGP);
+        let row = self.frame.row_mut(ly);
+        for (shade, color) in row.iter_mut().zip(self.line.iter()) {
+            *shade = (bgp >> (color * 2)) & 0x03;
+        }
+        if mmu.lcd.lcdc & 0x02 > 0 {
+            self.render_sprites(mmu);
+        }
     }
 
-    pub fn update(&mut self) -> bool {
-        let mut is_updated = self.window.is_open() && !self.window.is_key_down(Key::Escape);
-        if is_updated {
-            self.window.update();
+    fn sprite_height(mmu: &MMU) -> u8 {
+        if mmu.lcd.lcdc & 0x04 > 0 {
+            16
+        } else {
+            8
         }
-        is_updated
     }
-}
\ No newline at end of file
+
+    /// Picks the sprites for line LY, the hardware does this during mode 2
+    pub fn scan_oam(&mut self, mmu: &MMU) {
+        self.sprites = sprite::scan(mmu, mmu.lcd.ly(), PPU::sprite_height(mmu));
+    }
+
+    /// Sprites over line LY. For every pixel the first opaque sprite in priority order wins, and if it has the
+    /// BG-over-OBJ bit set it's still hidden behind background colors 1-3.
+    pub fn render_sprites(&mut self, mmu: &mut MMU) {
+        let ly = mmu.lcd.ly();
+        let (obp0, obp1) = (mmu.io.get(io::OBP0), mmu.io.get(io::OBP1));
+        let row = self.frame.row_mut(ly as usize);
+        for (x, shade) in row.iter_mut().enumerate() {
+            for sprite in &self.sprites {
+                let column = match sprite.column(x) {
+                    Some(column) if sprite.x_flip() => 7 - column,
+                    Some(column) => column,
+                    None => continue,
+                };
+                let (tile, y) = sprite.tile_row(ly);
+                let color = mmu.cram.tile(tile)[y][column];
+                if color == 0 {
+                    continue;
+                }
+                if !sprite.behind_background() || self.line[x] == 0 {
+                    let palette = if sprite.uses_obp1() { obp1 } else { obp0 };
+                    *shade = (palette >> (color * 2)) & 0x03;
+                }
+                break;
+            }
+        }
+    }
+
+    /// Background color numbers for line LY, scrolled by SCX/SCY and wrapping around the 256x256 map
+    pub fn render_background(&mut self, mmu: &mut MMU) {
+        let lcdc = mmu.lcd.lcdc;
+        let y = mmu.lcd.ly().wrapping_add(mmu.lcd.scy) as usize;
+        let addressing = TileAddressing::from_lcdc(lcdc);
+        let map = if lcdc & 0x08 > 0 { &mmu.bgmapdata2 } else { &mmu.bgmapdata1 };
+        let cram = &mut mmu.cram;
+        for (x, color) in self.line.iter_mut().enumerate() {
+            let x = (x as u8).wrapping_add(mmu.lcd.scx) as usize;
+            let tile = cram.tile(map.tile_number(x / 8, y / 8, addressing));
+            *color = tile[y % 8][x % 8];
+        }
+    }
+
+    /// Window color numbers over the background for line LY. The window's top left corner sits at (WX-7, WY), and its
+    /// rows come from the internal line counter rather than LY, so hiding it for a few lines doesn't skip any of them.
+    pub fn render_window(&mut self, mmu: &mut MMU) {
+        let lcd = &mmu.lcd;
+        let (lcdc, wx) = (lcd.lcdc, lcd.wx as usize);
+        if lcdc & 0x20 == 0 || lcd.ly() < lcd.wy || wx >= SCREEN_WIDTH + 7 {
+            return;
+        }
+        let y = self.window_line as usize;
+        let addressing = TileAddressing::from_lcdc(lcdc);
+        let map = if lcdc & 0x40 > 0 { &mmu.bgmapdata2 } else { &mmu.bgmapdata1 };
+        let cram = &mut mmu.cram;
+        for (screen_x, color) in self.line.iter_mut().enumerate().skip(wx.saturating_sub(7)) {
+            let x = screen_x + 7 - wx;
+            let tile = cram.tile(map.tile_number(x / 8, y / 8, addressing));
+            *color = tile[y % 8][x % 8];
+        }
+        self.window_line = self.window_line.wrapping_add(1);
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,72 @@
+use memory_bus::dma::{OAM_SIZE, OAM_START};
+use memory_bus::MMU;
+
+pub const SPRITES_PER_LINE: usize = 10;
+const ENTRY_SIZE: usize = 4;
+
+/// One OAM entry. Positions are stored as in OAM, offset by 16 (y) and 8 (x) so sprites can hang off the top and left.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sprite {
+    pub y: u8,
+    pub x: u8,
+    pub tile: u8,
+    pub flags: u8,
+    pub index: usize,
+    /// 8 or 16, whatever LCDC said when the sprite was picked. Games flipping the size mid-line don't change it.
+    pub height: u8,
+}
+
+impl Sprite {
+    pub fn behind_background(&self) -> bool {
+        self.flags & 0x80 > 0
+    }
+
+    pub fn y_flip(&self) -> bool {
+        self.flags & 0x40 > 0
+    }
+
+    pub fn x_flip(&self) -> bool {
+        self.flags & 0x20 > 0
+    }
+
+    /// OBP1 instead of OBP0
+    pub fn uses_obp1(&self) -> bool {
+        self.flags & 0x10 > 0
+    }
+
+    /// Tile and the row inside it that line `ly` crosses, `ly` has to be one the sprite was picked for
+    pub fn tile_row(&self, ly: u8) -> (usize, usize) {
+        let mut y = ly as usize + 16 - self.y as usize;
+        if self.y_flip() {
+            y = self.height as usize - 1 - y;
+        }
+        // 8x16 sprites ignore the lowest bit of the tile number
+        let tile = if self.height == 16 { self.tile & 0xFE } else { self.tile } as usize + y / 8;
+        (tile, y % 8)
+    }
+
+    /// Column inside the sprite that screen column `x` falls on, if any
+    pub fn column(&self, x: usize) -> Option<usize> {
+        let column = (x + 8).checked_sub(self.x as usize)?;
+        if column < 8 {
+            Some(column)
+        } else {
+            None
+        }
+    }
+}
+
+/// The mode 2 scan: the first 10 sprites in OAM order that overlap line `ly`, whatever their X, sorted into DMG
+/// drawing priority (smaller X first, then lower OAM index).
+pub fn scan(mmu: &MMU, ly: u8, height: u8) -> Vec<Sprite> {
+    let oam = &mmu.ram[OAM_START as usize..(OAM_START + OAM_SIZE) as usize];
+    let line = ly as usize + 16;
+    let mut sprites: Vec<Sprite> = oam.chunks(ENTRY_SIZE)
+        .enumerate()
+        .map(|(index, entry)| Sprite { y: entry[0], x: entry[1], tile: entry[2], flags: entry[3], index, height })
+        .filter(|sprite| (sprite.y as usize..sprite.y as usize + height as usize).contains(&line))
+        .take(SPRITES_PER_LINE)
+        .collect();
+    sprites.sort_by_key(|sprite| (sprite.x, sprite.index));
+    sprites
+}
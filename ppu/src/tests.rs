@@ -0,0 +1,484 @@
+use super::*;
+use memory_bus::{io, PPUMode};
+
+fn prerequisites() -> (PPU, MMU) {
+    let mut mmu = MMU::new();
+    mmu.write_byte(io::LCDC, 0x91);
+    (PPU::default(), mmu)
+}
+
+/// Steps the PPU and the bus together M-cycle by M-cycle, like the emulator loop does
+fn run(ppu: &mut PPU, mmu: &mut MMU, clocks: usize) {
+    for _ in 0..clocks / 4 {
+        ppu.step(mmu, 4);
+        mmu.tick(4);
+    }
+}
+
+#[test]
+fn mode_timing_test() {
+    let (mut ppu, mut mmu) = prerequisites();
+    run(&mut ppu, &mut mmu, 4);
+    assert_eq!(mmu.read_byte(io::STAT), 0x82 | 0x04); // mode 2, LY == LYC == 0
+    run(&mut ppu, &mut mmu, 80);
+    assert_eq!(mmu.read_byte(io::STAT) & 0x03, 3);
+    run(&mut ppu, &mut mmu, 172);
+    assert_eq!(mmu.read_byte(io::STAT) & 0x03, 0);
+    run(&mut ppu, &mut mmu, 204);
+    assert_eq!(mmu.read_byte(io::LY), 1);
+    assert_eq!(mmu.read_byte(io::STAT), 0x82);
+
+    run(&mut ppu, &mut mmu, 456 * 142);
+    assert_eq!(mmu.read_byte(io::LY), 143);
+    assert!(!mmu.interrupts.is_requested(Interrupt::VBlank));
+    run(&mut ppu, &mut mmu, 456);
+    assert_eq!(mmu.read_byte(io::LY), 144);
+    assert_eq!(mmu.read_byte(io::STAT) & 0x03, 1);
+    assert!(mmu.interrupts.is_requested(Interrupt::VBlank));
+
+    run(&mut ppu, &mut mmu, 456 * 10);
+    assert_eq!(mmu.read_byte(io::LY), 0);
+    assert_eq!(mmu.read_byte(io::STAT) & 0x03, 2);
+
+    mmu.write_byte(io::LY, 0x42); // read only
+    assert_eq!(mmu.read_byte(io::LY), 0);
+    mmu.write_byte(io::LCDC, 0x11);
+    run(&mut ppu, &mut mmu, 456 * 2);
+    assert_eq!(mmu.read_byte(io::LY), 0);
+    assert_eq!(mmu.read_byte(io::STAT) & 0x03, 0);
+}
+
+#[test]
+fn stat_interrupt_test() {
+    let (mut ppu, mut mmu) = prerequisites();
+    mmu.write_byte(io::LYC, 2);
+    mmu.write_byte(io::STAT, 0xFF);
+    assert_eq!(mmu.read_byte(io::STAT), 0xF8); // nothing running yet, mode bits are read only
+    mmu.tick(4);
+    assert!(mmu.interrupts.is_requested(Interrupt::Stat)); // selecting HBlank while in mode 0 is an edge too
+    mmu.interrupts.clear(Interrupt::Stat);
+    mmu.write_byte(io::STAT, 0x40); // LY == LYC
+    run(&mut ppu, &mut mmu, 456 * 2 - 4);
+    assert!(!mmu.interrupts.is_requested(Interrupt::Stat));
+    run(&mut ppu, &mut mmu, 4);
+    assert_eq!(mmu.read_byte(io::LY), 2);
+    assert!(mmu.interrupts.is_requested(Interrupt::Stat));
+    mmu.interrupts.clear(Interrupt::Stat);
+
+    // HBlank holds the line up when LY becomes 3 == LYC, so the coincidence doesn't make a new edge
+    mmu.write_byte(io::LYC, 0xFF);
+    mmu.write_byte(io::STAT, 0x48);
+    run(&mut ppu, &mut mmu, 80 + 172);
+    assert!(mmu.interrupts.is_requested(Interrupt::Stat)); // HBlank of line 2
+    mmu.interrupts.clear(Interrupt::Stat);
+    mmu.write_byte(io::LYC, 3);
+    run(&mut ppu, &mut mmu, 204);
+    assert_eq!(mmu.read_byte(io::LY), 3);
+    assert!(!mmu.interrupts.is_requested(Interrupt::Stat));
+    run(&mut ppu, &mut mmu, 80 + 172);
+    assert!(!mmu.interrupts.is_requested(Interrupt::Stat)); // still held by the coincidence
+}
+
+/// Fills a tile with the same 2bpp row over and over
+fn write_tile(mmu: &mut MMU, at: u16, low: u8, high: u8) {
+    for row in 0..8 {
+        mmu.write_byte(at + row * 2, low);
+        mmu.write_byte(at + row * 2 + 1, high);
+    }
+}
+
+#[test]
+fn background_test() {
+    let (mut ppu, mut mmu) = prerequisites();
+    mmu.write_byte(io::BGP, 0xE4);
+    write_tile(&mut mmu, 0x8010, 0xFF, 0x00); // tile 1, color 1
+    write_tile(&mut mmu, 0x8020, 0xF0, 0xF0); // tile 2, color 3 then 0
+    mmu.write_byte(0x9800, 1);
+    mmu.write_byte(0x9801, 2);
+    mmu.write_byte(0x9820, 2);
+
+    ppu.render_scanline(&mut mmu);
+    assert_eq!(ppu.frame.shades()[0..16], [1, 1, 1, 1, 1, 1, 1, 1, 3, 3, 3, 3, 0, 0, 0, 0]);
+    assert!(ppu.frame.shades()[16..SCREEN_WIDTH].iter().all(|shade| *shade == 0));
+
+    mmu.write_byte(io::SCX, 4);
+    ppu.render_scanline(&mut mmu);
+    assert_eq!(ppu.frame.shades()[0..12], [1, 1, 1, 1, 3, 3, 3, 3, 0, 0, 0, 0]);
+    mmu.write_byte(io::SCX, 252); // wraps around to map column 31
+    ppu.render_scanline(&mut mmu);
+    assert_eq!(ppu.frame.shades()[0..12], [0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1]);
+    mmu.write_byte(io::SCX, 0);
+    mmu.write_byte(io::SCY, 8);
+    ppu.render_scanline(&mut mmu);
+    assert_eq!(ppu.frame.shades()[0..8], [3, 3, 3, 3, 0, 0, 0, 0]);
+
+    mmu.write_byte(io::SCY, 0);
+    mmu.write_byte(io::LCDC, 0x81); // 0x8800 addressing, index 1 is tile 257 at 0x9010
+    write_tile(&mut mmu, 0x9010, 0x00, 0xFF);
+    mmu.write_byte(io::BGP, 0x1B);
+    ppu.render_scanline(&mut mmu);
+    assert_eq!(ppu.frame.shades()[0..8], [1; 8]); // color 2 through the reversed palette
+
+    mmu.write_byte(io::LCDC, 0x99); // 0x9C00 map, which is all tile 0
+    ppu.render_scanline(&mut mmu);
+    assert_eq!(ppu.frame.shades()[0..8], [3; 8]);
+    mmu.write_byte(io::LCDC, 0x90); // background off
+    mmu.write_byte(io::BGP, 0xE4);
+    ppu.render_scanline(&mut mmu);
+    assert_eq!(ppu.frame.shades()[0..8], [0; 8]);
+}
+
+#[test]
+fn scanline_timing_test() {
+    let (mut ppu, mut mmu) = prerequisites();
+    mmu.write_byte(io::BGP, 0xE4);
+    write_tile(&mut mmu, 0x8000, 0xFF, 0xFF);
+    run(&mut ppu, &mut mmu, 80 + 172 - 4);
+    assert_eq!(ppu.frame.shades()[0], 0);
+    run(&mut ppu, &mut mmu, 4);
+    assert_eq!(ppu.frame.shades()[0..SCREEN_WIDTH], [3; SCREEN_WIDTH]);
+    assert_eq!(ppu.frame.shades()[SCREEN_WIDTH], 0);
+}
+
+/// Draws line `ly` on its own, without going through the mode timing
+fn render_line(ppu: &mut PPU, mmu: &mut MMU, ly: u8) -> Vec<u8> {
+    mmu.lcd.set_ly(ly);
+    ppu.scan_oam(mmu);
+    ppu.render_scanline(mmu);
+    let start = ly as usize * SCREEN_WIDTH;
+    ppu.frame.shades()[start..start + SCREEN_WIDTH].to_vec()
+}
+
+#[test]
+fn window_test() {
+    let (mut ppu, mut mmu) = prerequisites();
+    mmu.write_byte(io::LCDC, 0xF1); // window on, window map at 0x9C00, background map at 0x9800
+    mmu.write_byte(io::BGP, 0xE4);
+    write_tile(&mut mmu, 0x8010, 0xFF, 0x00); // tile 1, color 1
+    write_tile(&mut mmu, 0x8020, 0xFF, 0xFF); // tile 2, color 3
+    for column in 0..32 {
+        mmu.write_byte(0x9C00 + column, 1);
+        mmu.write_byte(0x9C20 + column, 2);
+    }
+    mmu.write_byte(io::WY, 2);
+    mmu.write_byte(io::WX, 7 + 80);
+
+    assert_eq!(render_line(&mut ppu, &mut mmu, 1), vec![0; SCREEN_WIDTH]);
+    let line = render_line(&mut ppu, &mut mmu, 2);
+    assert_eq!(line[..80], [0; 80]);
+    assert_eq!(line[80..], [1; 80]);
+
+    // hidden off the right edge for a few lines, the counter holds still meanwhile
+    mmu.write_byte(io::WX, 167);
+    for ly in 3..6 {
+        assert_eq!(render_line(&mut ppu, &mut mmu, ly), vec![0; SCREEN_WIDTH]);
+    }
+    mmu.write_byte(io::WX, 7 + 80);
+    for ly in 6..10 {
+        render_line(&mut ppu, &mut mmu, ly);
+    }
+    assert_eq!(render_line(&mut ppu, &mut mmu, 10)[80], 1); // window line 5, LY - WY would be 8
+    for ly in 11..13 {
+        render_line(&mut ppu, &mut mmu, ly);
+    }
+    assert_eq!(render_line(&mut ppu, &mut mmu, 13)[80], 3);
+
+    mmu.write_byte(io::LCDC, 0xD1); // window off
+    assert_eq!(render_line(&mut ppu, &mut mmu, 14), vec![0; SCREEN_WIDTH]);
+    mmu.write_byte(io::LCDC, 0xF0); // background and window off together on DMG
+    assert_eq!(render_line(&mut ppu, &mut mmu, 14), vec![0; SCREEN_WIDTH]);
+}
+
+#[test]
+fn window_left_edge_test() {
+    let (mut ppu, mut mmu) = prerequisites();
+    mmu.write_byte(io::LCDC, 0xB1); // window on, both layers on the 0x9800 map
+    mmu.write_byte(io::BGP, 0xE4);
+    write_tile(&mut mmu, 0x8010, 0x01, 0x01); // tile 1, only the rightmost column is color 3
+    mmu.write_byte(0x9800, 1);
+    mmu.write_byte(io::WX, 0); // WX below 7 pushes the window's first columns off screen
+    let line = render_line(&mut ppu, &mut mmu, 0);
+    assert_eq!(line[0..2], [3, 0]);
+
+    // the counter starts over every frame
+    let (mut ppu, mut mmu) = prerequisites();
+    mmu.write_byte(io::LCDC, 0xF1);
+    mmu.write_byte(io::BGP, 0xE4);
+    mmu.write_byte(io::WX, 7);
+    write_tile(&mut mmu, 0x8010, 0xFF, 0xFF);
+    mmu.write_byte(0x9C20, 1);
+    run(&mut ppu, &mut mmu, 456 * 154 + 80 + 172);
+    assert_eq!(ppu.frame.shades()[0], 0); // window row 0 again, not 144
+    run(&mut ppu, &mut mmu, 456 * 8);
+    assert_eq!(ppu.frame.shades()[8 * SCREEN_WIDTH], 3);
+}
+
+fn write_sprite(mmu: &mut MMU, index: u16, y: u8, x: u8, tile: u8, flags: u8) {
+    for (offset, byte) in [y, x, tile, flags].iter().enumerate() {
+        mmu.write_byte(0xFE00 + index * 4 + offset as u16, *byte);
+    }
+}
+
+fn sprite_prerequisites() -> (PPU, MMU) {
+    let (ppu, mut mmu) = prerequisites();
+    mmu.write_byte(io::LCDC, 0x93); // sprites on, 8x8
+    mmu.write_byte(io::BGP, 0xE4);
+    mmu.write_byte(io::OBP0, 0xE4);
+    mmu.write_byte(io::OBP1, 0x1B);
+    write_tile(&mut mmu, 0x8010, 0xFF, 0x00); // tile 1, color 1
+    write_tile(&mut mmu, 0x8020, 0x00, 0xF0); // tile 2, color 2 on the left half
+    write_tile(&mut mmu, 0x8030, 0xFF, 0xFF); // tile 3, color 3
+    (ppu, mmu)
+}
+
+#[test]
+fn sprite_test() {
+    let (mut ppu, mut mmu) = sprite_prerequisites();
+    write_sprite(&mut mmu, 0, 16, 8, 1, 0x00); // top left corner
+    write_sprite(&mut mmu, 1, 16, 8 + 16, 2, 0x00);
+    write_sprite(&mut mmu, 2, 16, 8 + 32, 2, 0x20); // x flip
+    write_sprite(&mut mmu, 3, 16, 8 + 48, 1, 0x10); // OBP1
+    write_sprite(&mut mmu, 4, 14, 8 + 64, 1, 0x00); // 2 lines above the screen
+    let line = render_line(&mut ppu, &mut mmu, 0);
+    assert_eq!(line[0..8], [1; 8]);
+    assert_eq!(line[16..24], [2, 2, 2, 2, 0, 0, 0, 0]);
+    assert_eq!(line[32..40], [0, 0, 0, 0, 2, 2, 2, 2]);
+    assert_eq!(line[48..56], [2; 8]);
+    assert_eq!(line[64..72], [1; 8]);
+    assert_eq!(render_line(&mut ppu, &mut mmu, 6)[64..72], [0; 8]);
+
+    // BG-over-OBJ only lets background color 0 through
+    mmu.write_byte(0x9800, 3);
+    write_sprite(&mut mmu, 0, 16, 4, 1, 0x80);
+    let line = render_line(&mut ppu, &mut mmu, 0);
+    assert_eq!(line[0..8], [3; 8]);
+    assert_eq!(line[8..12], [0; 4]);
+    mmu.write_byte(io::LCDC, 0x91); // sprites off
+    assert_eq!(render_line(&mut ppu, &mut mmu, 0)[16..24], [0; 8]);
+}
+
+#[test]
+fn sprite_priority_test() {
+    let (mut ppu, mut mmu) = sprite_prerequisites();
+    write_sprite(&mut mmu, 0, 16, 8 + 4, 3, 0x00);
+    write_sprite(&mut mmu, 1, 16, 8 + 2, 1, 0x00); // smaller X wins over the lower index
+    write_sprite(&mut mmu, 2, 16, 8 + 20, 1, 0x00);
+    write_sprite(&mut mmu, 3, 16, 8 + 20, 3, 0x00); // same X, lower index wins
+    write_sprite(&mut mmu, 4, 16, 8 + 40, 2, 0x00);
+    write_sprite(&mut mmu, 5, 16, 8 + 40, 3, 0x00); // transparent pixels of the winner let the next one through
+    let line = render_line(&mut ppu, &mut mmu, 0);
+    assert_eq!(line[2..12], [1, 1, 1, 1, 1, 1, 1, 1, 3, 3]);
+    assert_eq!(line[20..28], [1; 8]);
+    assert_eq!(line[40..48], [2, 2, 2, 2, 3, 3, 3, 3]);
+
+    // only the first 10 in OAM order make it, sprites off the left edge count as well
+    for index in 0..40 {
+        write_sprite(&mut mmu, index, 0, 0, 0, 0);
+    }
+    write_sprite(&mut mmu, 0, 16, 0, 1, 0x00);
+    for index in 1..11 {
+        write_sprite(&mut mmu, index, 16, 8 + 8 * index as u8, 1, 0x00);
+    }
+    let line = render_line(&mut ppu, &mut mmu, 0);
+    assert_eq!(line[8..80], [1; 72]);
+    assert_eq!(line[80..88], [0; 8]);
+}
+
+#[test]
+fn tall_sprite_test() {
+    let (mut ppu, mut mmu) = sprite_prerequisites();
+    mmu.write_byte(io::LCDC, 0x97); // 8x16
+    write_sprite(&mut mmu, 0, 16, 8, 3, 0x00); // tiles 2 and 3, the low bit is ignored
+    write_sprite(&mut mmu, 1, 16, 8 + 16, 3, 0x40); // y flip swaps the halves too
+    let line = render_line(&mut ppu, &mut mmu, 0);
+    assert_eq!(line[0..8], [2, 2, 2, 2, 0, 0, 0, 0]);
+    assert_eq!(line[16..24], [3; 8]);
+    let line = render_line(&mut ppu, &mut mmu, 15);
+    assert_eq!(line[0..8], [3; 8]);
+    assert_eq!(line[16..24], [2, 2, 2, 2, 0, 0, 0, 0]);
+    assert_eq!(render_line(&mut ppu, &mut mmu, 16)[0..8], [0; 8]);
+}
+
+#[test]
+fn sprite_size_change_test() {
+    // 8x16 while OAM is scanned, 8x8 by the time mode 3 ends, the line keeps the sprites it picked
+    let (mut ppu, mut mmu) = sprite_prerequisites();
+    mmu.write_byte(io::LCDC, 0x97);
+    write_sprite(&mut mmu, 0, 16, 8, 3, 0x40);
+    run(&mut ppu, &mut mmu, 456 * 10 + 80 + 4);
+    mmu.write_byte(io::LCDC, 0x93);
+    run(&mut ppu, &mut mmu, 456 - 80 - 4);
+    assert_eq!(ppu.frame.shades()[10 * SCREEN_WIDTH..10 * SCREEN_WIDTH + 8], [2, 2, 2, 2, 0, 0, 0, 0]);
+
+    let (mut ppu, mut mmu) = fifo_prerequisites();
+    mmu.write_byte(io::LCDC, 0x97);
+    write_sprite(&mut mmu, 0, 16, 8, 3, 0x40);
+    run(&mut ppu, &mut mmu, 456 * 10 + 80 + 4);
+    mmu.write_byte(io::LCDC, 0x93);
+    run(&mut ppu, &mut mmu, 456 - 80 - 4);
+    assert_eq!(ppu.frame.shades()[10 * SCREEN_WIDTH..10 * SCREEN_WIDTH + 8], [2, 2, 2, 2, 0, 0, 0, 0]);
+}
+
+fn fifo_prerequisites() -> (PPU, MMU) {
+    let (mut ppu, mmu) = sprite_prerequisites();
+    ppu.renderer = Renderer::Fifo;
+    (ppu, mmu)
+}
+
+/// Runs the first line dot by dot and returns how long mode 3 took
+fn mode3_dots(ppu: &mut PPU, mmu: &mut MMU) -> usize {
+    let mut dots = 0;
+    for _ in 0..LINE_CLOCKS {
+        ppu.step(mmu, 1);
+        if mmu.ppu_mode() == PPUMode::ScanlineVRAM {
+            dots += 1;
+        }
+    }
+    assert_eq!(mmu.lcd.ly(), 1);
+    dots
+}
+
+#[test]
+fn fifo_timing_test() {
+    let (mut ppu, mut mmu) = fifo_prerequisites();
+    assert_eq!(mode3_dots(&mut ppu, &mut mmu), 172);
+
+    let (mut ppu, mut mmu) = fifo_prerequisites();
+    mmu.write_byte(io::SCX, 3);
+    assert_eq!(mode3_dots(&mut ppu, &mut mmu), 175);
+
+    let (mut ppu, mut mmu) = fifo_prerequisites();
+    write_sprite(&mut mmu, 0, 16, 8 + 20, 1, 0x00);
+    write_sprite(&mut mmu, 1, 16, 8 + 100, 1, 0x00);
+    assert_eq!(mode3_dots(&mut ppu, &mut mmu), 172 + 2 * 6);
+
+    let (mut ppu, mut mmu) = fifo_prerequisites();
+    mmu.write_byte(io::LCDC, 0xB3); // window on
+    mmu.write_byte(io::WX, 7 + 80);
+    assert_eq!(mode3_dots(&mut ppu, &mut mmu), 172 + 6);
+    // HBlank takes up the slack, lines stay 456 dots
+    run(&mut ppu, &mut mmu, 456 * 153);
+    assert_eq!(mmu.lcd.ly(), 0);
+}
+
+#[test]
+fn fifo_matches_scanline_test() {
+    // the window in the middle, and starting left of the screen with its first columns cut off
+    for wx in [7 + 100, 3].iter() {
+        let (mut scanline, mut mmu) = sprite_prerequisites();
+        mmu.write_byte(io::LCDC, 0xF3); // window on, window map at 0x9C00
+        for column in 0..32 {
+            mmu.write_byte(0x9800 + column, (column % 4) as u8);
+            mmu.write_byte(0x9820 + column, 3 - (column % 4) as u8);
+            mmu.write_byte(0x9C00 + column, 2);
+        }
+        mmu.write_byte(io::SCX, 13);
+        mmu.write_byte(io::SCY, 3);
+        mmu.write_byte(io::WY, 4);
+        mmu.write_byte(io::WX, *wx);
+        write_sprite(&mut mmu, 0, 16, 4, 1, 0x00); // off the left edge
+        write_sprite(&mut mmu, 1, 14, 8 + 30, 2, 0x20);
+        write_sprite(&mut mmu, 2, 18, 8 + 34, 3, 0x10); // overlaps sprite 1
+        write_sprite(&mut mmu, 3, 16, 8 + 60, 3, 0x80); // behind the background
+        write_sprite(&mut mmu, 4, 16, 8 + 104, 2, 0x00); // over the window
+
+        let mut fifo = PPU { renderer: Renderer::Fifo, ..PPU::default() };
+        let mut fifo_mmu = sprite_prerequisites().1;
+        fifo_mmu.write_byte(io::LCDC, 0xF3);
+        for at in 0x8000..0xA000 {
+            fifo_mmu.write_byte(at, mmu.read_byte(at));
+        }
+        for at in 0xFE00..0xFEA0 {
+            fifo_mmu.write_byte(at, mmu.read_byte(at));
+        }
+        for at in [io::SCX, io::SCY, io::WY, io::WX].iter() {
+            fifo_mmu.write_byte(*at, mmu.read_byte(*at));
+        }
+        run(&mut scanline, &mut mmu, 456 * 16);
+        run(&mut fifo, &mut fifo_mmu, 456 * 16);
+        assert_eq!(fifo.frame.shades()[..16 * SCREEN_WIDTH], scanline.frame.shades()[..16 * SCREEN_WIDTH]);
+        assert!(fifo.frame.shades()[4 * SCREEN_WIDTH..16 * SCREEN_WIDTH].contains(&2)); // the window made it
+    }
+}
+
+#[test]
+fn fifo_mid_scanline_test() {
+    let (mut ppu, mut mmu) = fifo_prerequisites();
+    write_tile(&mut mmu, 0x8000, 0xFF, 0x00); // all color 1
+    // BGP changes 40 dots into mode 3, the pixels after it come out in the new shade
+    run(&mut ppu, &mut mmu, 80 + 40);
+    mmu.write_byte(io::BGP, 0xE0); // color 1 goes white
+    run(&mut ppu, &mut mmu, 456 - 80 - 40);
+    let line = &ppu.frame.shades()[..SCREEN_WIDTH];
+    assert_eq!(line[..28], [1; 28]); // the first pixel leaves the FIFO on dot 13
+    assert_eq!(line[28..], [0; SCREEN_WIDTH - 28]);
+
+    // the scanline renderer only sees the palette the line ends with
+    let (mut ppu, mut mmu) = sprite_prerequisites();
+    write_tile(&mut mmu, 0x8000, 0xFF, 0x00);
+    run(&mut ppu, &mut mmu, 80 + 40);
+    mmu.write_byte(io::BGP, 0xE0);
+    run(&mut ppu, &mut mmu, 456 - 80 - 40);
+    assert_eq!(ppu.frame.shades()[..SCREEN_WIDTH], [0; SCREEN_WIDTH]);
+}
+
+#[test]
+fn frame_buffer_test() {
+    let (mut ppu, mut mmu) = prerequisites();
+    mmu.write_byte(io::BGP, 0xE4);
+    write_tile(&mut mmu, 0x8000, 0x00, 0xFF); // color 2 everywhere
+    run(&mut ppu, &mut mmu, 456 * 144 - 4);
+    assert!(!ppu.frame_ready());
+    run(&mut ppu, &mut mmu, 4);
+    assert!(ppu.frame_ready());
+    assert!(!ppu.frame_ready()); // once per frame
+    run(&mut ppu, &mut mmu, 456 * 154);
+    assert!(ppu.frame_ready());
+
+    assert_eq!(ppu.frame.shade(159, 143), 2);
+    let rgba = ppu.frame.to_rgba(&framebuffer::GRAYSCALE);
+    assert_eq!(rgba.len(), SCREEN_WIDTH * SCREEN_HEIGHT * 4);
+    assert_eq!(rgba[0..4], [0x55, 0x55, 0x55, 0xFF]);
+
+    let mut ppm = Vec::new();
+    ppu.frame.write_ppm(&framebuffer::GRAYSCALE, &mut ppm).unwrap();
+    let header = b"P6\n160 144\n255\n";
+    assert_eq!(ppm[..header.len()], header[..]);
+    assert_eq!(ppm.len(), header.len() + SCREEN_WIDTH * SCREEN_HEIGHT * 3);
+    assert_eq!(ppm[header.len()..header.len() + 3], [0x55, 0x55, 0x55]);
+}
+
+#[test]
+fn blit_test() {
+    let (mut ppu, mut mmu) = prerequisites();
+    mmu.write_byte(io::BGP, 0xE4);
+    write_tile(&mut mmu, 0x8000, 0xAA, 0x00); // color 1 and 0 in stripes
+    run(&mut ppu, &mut mmu, 456 * 144);
+    let palette = framebuffer::GRAYSCALE;
+    let (white, gray) = (0x00FF_FFFF, 0x00AA_AAAA);
+
+    // 2x, every pixel turns into a 2x2 block
+    let mut buffer = vec![0; 320 * 288];
+    ppu.frame.blit(&palette, &mut buffer, 320, 288);
+    assert_eq!(buffer[0..6], [gray, gray, white, white, gray, gray]);
+    assert_eq!(buffer[320..326], buffer[0..6]);
+    assert_eq!(buffer[287 * 320 + 318..], [white, white]);
+
+    // too wide, bars left and right
+    let mut buffer = vec![1; 400 * 288];
+    ppu.frame.blit(&palette, &mut buffer, 400, 288);
+    assert_eq!(buffer[0..40], [0; 40]);
+    assert_eq!(buffer[40..42], [gray, gray]);
+    assert_eq!(buffer[360..400], [0; 40]);
+    // too tall, bars above and below
+    let mut buffer = vec![1; 160 * 164];
+    ppu.frame.blit(&palette, &mut buffer, 160, 164);
+    assert_eq!(buffer[..160 * 10], [0; 160 * 10][..]);
+    assert_eq!(buffer[160 * 10], gray);
+    assert_eq!(buffer[160 * 154..], [0; 160 * 10][..]);
+
+    // minimized, nothing to draw
+    ppu.frame.blit(&palette, &mut [], 0, 0);
+    ppu.frame.blit(&palette, &mut [], 0, 288);
+    ppu.frame.blit(&palette, &mut [], 320, 0);
+}
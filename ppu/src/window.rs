@@ -0,0 +1,106 @@
+use std::time::{Duration, Instant};
+
+use minifb::{Key, KeyRepeat, WindowOptions, Window};
+use memory_bus::joypad::Buttons;
+
+use crate::bindings::{Bindings, Hotkey};
+use crate::framebuffer::{self, FrameBuffer, Palette};
+use crate::{SCREEN_HEIGHT, SCREEN_WIDTH};
+
+pub const MAX_SCALE: usize = 8;
+/// How often the FPS in the title is refreshed
+const FPS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// minifb frontend. Opens at an integer multiple of the screen size, and when resized keeps the aspect ratio with
+/// black bars around the picture.
+pub struct PPUWindow {
+    buffer: Vec<u32>,
+    window: Window,
+    bindings: Bindings,
+    palette: Palette,
+    title: String,
+    frames: u32,
+    fps_since: Instant,
+}
+
+impl PPUWindow {
+    /// `title` is usually the ROM name, `scale` is clamped to 1-8
+    pub fn new(title: &str, scale: usize) -> minifb::Result<PPUWindow> {
+        let scale = scale.clamp(1, MAX_SCALE);
+        let (width, height) = (SCREEN_WIDTH * scale, SCREEN_HEIGHT * scale);
+        let options = WindowOptions { resize: true, ..WindowOptions::default() };
+        let window = Window::new(title, width, height, options)?;
+        Ok(PPUWindow {
+            buffer: vec![0; width * height],
+            window,
+            bindings: Bindings::default(),
+            palette: framebuffer::GRAYSCALE,
+            title: title.to_string(),
+            frames: 0,
+            fps_since: Instant::now(),
+        })
+    }
+
+    /// Pumps input without drawing, returns false once the window is closed or Escape is pressed
+    pub fn update(&mut self) -> bool {
+        let is_open = self.window.is_open() && !self.window.is_key_down(Key::Escape);
+        if is_open {
+            self.window.update();
+        }
+        is_open
+    }
+
+    /// Shows a finished frame, scaled to the current window size. Returns false like `update`.
+    pub fn present(&mut self, frame: &FrameBuffer) -> minifb::Result<bool> {
+        if !self.window.is_open() || self.window.is_key_down(Key::Escape) {
+            return Ok(false);
+        }
+        let (width, height) = self.window.get_size();
+        self.buffer.resize(width * height, 0);
+        frame.blit(&self.palette, &mut self.buffer, width, height);
+        self.window.update_with_buffer(&self.buffer)?;
+
+        self.frames += 1;
+        let elapsed = self.fps_since.elapsed();
+        if elapsed >= FPS_INTERVAL {
+            let fps = self.frames as f64 / elapsed.as_secs_f64();
+            self.window.set_title(&format!("{} - {:.1} FPS", self.title, fps));
+            self.frames = 0;
+            self.fps_since = Instant::now();
+        }
+        Ok(true)
+    }
+
+    pub fn with_bindings(mut self, bindings: Bindings) -> PPUWindow {
+        self.bindings = bindings;
+        self
+    }
+
+    pub fn with_palette(mut self, palette: Palette) -> PPUWindow {
+        self.palette = palette;
+        self
+    }
+
+    pub fn palette(&self) -> &Palette {
+        &self.palette
+    }
+
+    /// Currently held buttons, to be handed to `MMU::set_buttons`
+    pub fn buttons(&self) -> Buttons {
+        self.bindings.buttons(|key| self.window.is_key_down(key))
+    }
+
+    /// Hotkeys pressed since the last update, held keys don't repeat
+    pub fn pressed_hotkeys(&self) -> Vec<Hotkey> {
+        self.window.get_keys_pressed(KeyRepeat::No)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|key| self.bindings.hotkey(key))
+            .collect()
+    }
+
+    /// For hotkeys that act while held, like fast-forward
+    pub fn is_hotkey_down(&self, hotkey: Hotkey) -> bool {
+        self.bindings.hotkeys.iter().any(|(key, bound)| *bound == hotkey && self.window.is_key_down(*key))
+    }
+}